@@ -0,0 +1,24 @@
+const CRC32_POLYNOMIAL: u32 = 0xedb88320;
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for byte in bytes {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			let mask = (crc & 1).wrapping_neg();
+			crc = (crc >> 1) ^ (CRC32_POLYNOMIAL & mask);
+		}
+	}
+	!crc
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::checksum::crc32;
+
+	#[test]
+	fn crc32_check_value() {
+		assert_eq!(0xcbf43926, crc32(b"123456789"));
+		assert_eq!(0, crc32(b""));
+	}
+}
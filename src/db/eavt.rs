@@ -1,6 +1,7 @@
 use std::{fs, io};
 use std::path::Path;
 
+use crate::error::Result;
 use crate::kv_forest::{KvForest, RootIndex};
 
 #[cfg(test)]
@@ -14,62 +15,76 @@ mod tests {
 		let mut forest = Eavt::open(&forest_dir)?;
 		let root = forest.new_root()?;
 		let root = forest.push(root, 0, 0, "size".into(), 42)?;
-		let found = forest.find_t(root, &0, &0, &"size".into());
+		let found = forest.find_t(root, &0, &0, &"size".into())?;
 		assert_eq!(Some(42), found);
+		forest.commit(root)?;
+		drop(forest);
+		let forest = Eavt::open(&forest_dir)?;
+		assert_eq!(Some(42), forest.find_t(root, &0, &0, &"size".into())?);
 		Ok(())
 	}
 }
 
 pub struct Eavt {
-	eavt: KvForest<u32>,
-	avt: KvForest<u32>,
+	eavt: KvForest<u32, RootIndex>,
+	avt: KvForest<u32, RootIndex>,
 	vt: KvForest<String>,
+	avt_root: RootIndex,
+	vt_root: RootIndex,
 }
 
 impl Eavt {
-	pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
 		let path = path.as_ref();
 		if !path.is_dir() {
 			try_create_dir(path)?;
 		}
 		let vt = KvForest::<String>::open(&path.join("vt.forest"))?;
-		let avt = KvForest::<u32>::open(&path.join("avt.forest"))?;
-		let eavt = KvForest::<u32>::open(&path.join("eavt.forest"))?;
-		Ok(Self { vt, avt, eavt })
+		let avt = KvForest::<u32, RootIndex>::open(&path.join("avt.forest"))?;
+		let eavt = KvForest::<u32, RootIndex>::open(&path.join("eavt.forest"))?;
+		let (avt_root, vt_root) = (avt.latest_root(), vt.latest_root());
+		Ok(Self { vt, avt, eavt, avt_root, vt_root })
+	}
+	pub fn new_root(&mut self) -> Result<RootIndex> { self.eavt.add_root() }
+	pub fn commit(&mut self, root_index: RootIndex) -> Result<()> {
+		self.vt.commit(self.vt_root)?;
+		self.avt.commit(self.avt_root)?;
+		self.eavt.commit(root_index)
 	}
-	pub fn new_root(&mut self) -> io::Result<RootIndex> { self.eavt.add_root() }
 
-	pub fn find_t(&self, root_index: RootIndex, e: &u32, a: &u32, v: &String) -> Option<u32> {
+	pub fn find_t(&self, root_index: RootIndex, e: &u32, a: &u32, v: &String) -> Result<Option<u32>> {
 		let eavt_root = root_index;
-		let avt_root = match self.eavt.find(eavt_root, e) {
-			None => return None,
-			Some(found) => RootIndex::from(found)
+		let avt_root = match self.eavt.find(eavt_root, e)? {
+			None => return Ok(None),
+			Some(found) => found,
 		};
-		let vt_root = match self.avt.find(avt_root, a) {
-			None => return None,
-			Some(found) => RootIndex::from(found)
+		let vt_root = match self.avt.find(avt_root, a)? {
+			None => return Ok(None),
+			Some(found) => found,
 		};
 		let t = self.vt.find(vt_root, v);
 		t
 	}
 
-	pub fn push(&mut self, root_index: RootIndex, e: u32, a: u32, v: String, t: u32) -> io::Result<RootIndex> {
+	pub fn push(&mut self, root_index: RootIndex, e: u32, a: u32, v: String, t: u32) -> Result<RootIndex> {
 		let eavt_root = root_index;
-		let avt_root = match self.eavt.find(eavt_root, &e) {
+		let avt_root = match self.eavt.find(eavt_root, &e)? {
 			None => self.avt.add_root()?,
-			Some(found) => RootIndex::from(found),
+			Some(found) => found,
 		};
-		let vt_root = match self.avt.find(avt_root, &a) {
+		let vt_root = match self.avt.find(avt_root, &a)? {
 			None => self.vt.add_root()?,
-			Some(found) => RootIndex::from(found)
+			Some(found) => found,
 		};
-		let no_change = self.vt.find(vt_root, &v).map(|old| old == t).unwrap_or(false);
+		let no_change = self.vt.find(vt_root, &v)?.map(|old| old == t).unwrap_or(false);
 		let output = match no_change {
 			true => root_index,
 			false => {
 				let new_vt_root = self.vt.push(vt_root, v, t)?;
-				let new_avt_root = self.avt.push(avt_root, a, new_vt_root.to_u32())?;
-				let new_eavt_root = self.eavt.push(eavt_root, e, new_avt_root.to_u32())?;
+				let new_avt_root = self.avt.push(avt_root, a, new_vt_root)?;
+				let new_eavt_root = self.eavt.push(eavt_root, e, new_avt_root)?;
+				self.vt_root = new_vt_root;
+				self.avt_root = new_avt_root;
 				new_eavt_root
 			}
 		};
@@ -77,9 +92,9 @@ impl Eavt {
 	}
 }
 
-fn try_create_dir(path: impl AsRef<Path>) -> io::Result<()> {
+fn try_create_dir(path: impl AsRef<Path>) -> Result<()> {
 	if path.as_ref().exists() {
-		return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+		return Err(io::Error::from(io::ErrorKind::AlreadyExists).into());
 	}
 	fs::create_dir(path)?;
 	Ok(())
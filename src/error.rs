@@ -0,0 +1,71 @@
+use std::{fmt, io};
+use std::path::PathBuf;
+use std::string::FromUtf8Error;
+
+use crate::header::FileKind;
+use crate::item_stash::element::ElementStoreIndex;
+use crate::kv_forest::RootIndex;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+	Io(io::Error),
+	CorruptNode(ElementStoreIndex),
+	Checksum(ElementStoreIndex),
+	BadRootIndex(RootIndex),
+	InvalidUtf8Key(FromUtf8Error),
+	KeyTooLong { len: usize, max: usize },
+	KeyLength { len: usize, expected: usize },
+	KeyStoreFull,
+	UnsortedKeys,
+	BadMagic,
+	WrongFileKind { expected: FileKind, found: FileKind },
+	UnsupportedVersion { kind: FileKind, version: u16 },
+	TruncatedStore { path: PathBuf, length: u64, committed: u64 },
+	MisalignedStore { path: PathBuf, length: u64 },
+	CorruptRefs(serde_json::Error),
+	RefConflict { name: String, expected: Option<RootIndex>, found: Option<RootIndex> },
+	UnknownRef(String),
+	NotInHistory { name: String, root_index: RootIndex },
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Io(error) => write!(f, "i/o error: {}", error),
+			Error::CorruptNode(index) => write!(f, "corrupt node at element {}", index.0),
+			Error::Checksum(index) => write!(f, "checksum mismatch in node at element {}", index.0),
+			Error::BadRootIndex(index) => write!(f, "bad root index {:?}", index),
+			Error::InvalidUtf8Key(error) => write!(f, "invalid utf-8 key: {}", error),
+			Error::KeyTooLong { len, max } => write!(f, "key of {} bytes exceeds maximum of {} bytes", len, max),
+			Error::KeyLength { len, expected } => write!(f, "key of {} bytes where {} bytes were expected", len, expected),
+			Error::KeyStoreFull => write!(f, "key store is full"),
+			Error::UnsortedKeys => write!(f, "keys are duplicated or not in shard order"),
+			Error::BadMagic => write!(f, "file has no hamt header"),
+			Error::WrongFileKind { expected, found } => write!(f, "expected {:?} file but found {:?}", expected, found),
+			Error::UnsupportedVersion { kind, version } => write!(f, "unsupported {:?} file version {}", kind, version),
+			Error::TruncatedStore { path, length, committed } => write!(f, "{:?} holds {} bytes but {} were committed", path, length, committed),
+			Error::MisalignedStore { path, length } => write!(f, "{:?} ends partway through an element at {} bytes", path, length),
+			Error::CorruptRefs(error) => write!(f, "corrupt refs table: {}", error),
+			Error::RefConflict { name, expected, found } => write!(f, "ref {:?} is {:?}, expected {:?}", name, found, expected),
+			Error::UnknownRef(name) => write!(f, "unknown ref {:?}", name),
+			Error::NotInHistory { name, root_index } => write!(f, "{:?} is not in the history of ref {:?}", root_index, name),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(error) => Some(error),
+			Error::InvalidUtf8Key(error) => Some(error),
+			Error::CorruptRefs(error) => Some(error),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(error: io::Error) -> Self { Error::Io(error) }
+}
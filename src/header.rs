@@ -0,0 +1,140 @@
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+pub(crate) const HEADER_BYTES: u64 = 8;
+const MAGIC: [u8; 4] = *b"HAMT";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FileKind {
+	Elements = 1,
+	U32Keys = 2,
+	StringKeys = 3,
+	Values = 4,
+	Commits = 5,
+	Refs = 6,
+	BytesKeys = 7,
+	U64Keys = 8,
+	I64Keys = 9,
+	I32Keys = 10,
+}
+
+impl FileKind {
+	pub fn version(&self) -> u16 {
+		match self {
+			FileKind::Elements => 2,
+			FileKind::U32Keys => 2,
+			FileKind::StringKeys => 3,
+			FileKind::Values => 1,
+			FileKind::Commits => 4,
+			FileKind::Refs => 1,
+			FileKind::BytesKeys => 2,
+			FileKind::U64Keys => 1,
+			FileKind::I64Keys => 1,
+			FileKind::I32Keys => 1,
+		}
+	}
+	pub fn oldest_version(&self) -> u16 {
+		match self {
+			FileKind::Elements => 2,
+			FileKind::U32Keys => 2,
+			FileKind::Commits => 2,
+			_ => 1,
+		}
+	}
+	fn from_byte(byte: u8) -> Option<Self> {
+		match byte {
+			1 => Some(FileKind::Elements),
+			2 => Some(FileKind::U32Keys),
+			3 => Some(FileKind::StringKeys),
+			4 => Some(FileKind::Values),
+			5 => Some(FileKind::Commits),
+			6 => Some(FileKind::Refs),
+			7 => Some(FileKind::BytesKeys),
+			8 => Some(FileKind::U64Keys),
+			9 => Some(FileKind::I64Keys),
+			10 => Some(FileKind::I32Keys),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FileHeader {
+	pub kind: FileKind,
+	pub version: u16,
+}
+
+impl FileHeader {
+	pub fn current(kind: FileKind) -> Self {
+		Self { kind, version: kind.version() }
+	}
+	pub fn read(file: &File) -> Result<Self> {
+		let mut bytes = [0u8; HEADER_BYTES as usize];
+		match file.read_exact_at(&mut bytes, 0) {
+			Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Err(Error::BadMagic),
+			read => read?,
+		}
+		if bytes[0..4] != MAGIC {
+			return Err(Error::BadMagic);
+		}
+		let kind = FileKind::from_byte(bytes[4]).ok_or(Error::BadMagic)?;
+		let version = u16::from_be_bytes([bytes[6], bytes[7]]);
+		Ok(Self { kind, version })
+	}
+	pub fn to_bytes(&self) -> [u8; HEADER_BYTES as usize] {
+		let version = self.version.to_be_bytes();
+		[MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], self.kind as u8, 0, version[0], version[1]]
+	}
+}
+
+pub(crate) fn create_file(path: impl AsRef<Path>, kind: FileKind) -> Result<()> {
+	let file = OpenOptions::new().mode(0o600).create_new(true).write(true).open(path)?;
+	file.write_all_at(&FileHeader::current(kind).to_bytes(), 0)?;
+	Ok(())
+}
+
+pub(crate) fn check_file(file: &File, kind: FileKind) -> Result<FileHeader> {
+	let header = FileHeader::read(file)?;
+	if header.kind != kind {
+		return Err(Error::WrongFileKind { expected: kind, found: header.kind });
+	}
+	if header.version < kind.oldest_version() || header.version > kind.version() {
+		return Err(Error::UnsupportedVersion { kind, version: header.version });
+	}
+	Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::{File, OpenOptions};
+	use std::os::unix::fs::FileExt;
+
+	use crate::Error;
+	use crate::header::{check_file, create_file, FileHeader, FileKind};
+	use crate::tests::ready_test_dir;
+
+	#[test]
+	fn header_round_trip_and_rejections() {
+		let test_dir = ready_test_dir("header");
+		let path = test_dir.join("keys.stash");
+		create_file(&path, FileKind::StringKeys).expect("create");
+		let file = File::open(&path).expect("open");
+		assert_eq!(FileHeader::current(FileKind::StringKeys), check_file(&file, FileKind::StringKeys).expect("check"));
+		let error = check_file(&file, FileKind::Elements).expect_err("wrong kind");
+		assert!(matches!(error, Error::WrongFileKind { expected: FileKind::Elements, found: FileKind::StringKeys }));
+
+		let file = OpenOptions::new().read(true).write(true).open(&path).expect("reopen");
+		file.write_all_at(&[0xff, 0xff], 6).expect("write version");
+		let error = check_file(&file, FileKind::StringKeys).expect_err("future version");
+		assert!(matches!(error, Error::UnsupportedVersion { kind: FileKind::StringKeys, version: 0xffff }));
+
+		let raw = test_dir.join("raw");
+		std::fs::write(&raw, [0u8, 0, 0, 0]).expect("write raw");
+		let error = check_file(&File::open(&raw).expect("open raw"), FileKind::Elements).expect_err("no header");
+		assert!(matches!(error, Error::BadMagic));
+	}
+}
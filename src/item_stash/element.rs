@@ -1,5 +1,8 @@
 use std::ops::Add;
 
+use crate::checksum::crc32;
+use crate::header::HEADER_BYTES;
+
 pub(crate) const ELEMENT_BYTES: usize = 8;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -7,14 +10,20 @@ pub struct ElementStoreIndex(pub u32);
 
 impl ElementStoreIndex {
 	pub fn to_file_position(&self) -> u64 {
-		(self.0 as usize * ELEMENT_BYTES) as u64
+		HEADER_BYTES + (self.0 as usize * ELEMENT_BYTES) as u64
 	}
 	pub fn from_file_position(file_position: u64) -> Self {
-		let index = (file_position as usize / ELEMENT_BYTES) as u32;
+		let index = ((file_position - HEADER_BYTES) as usize / ELEMENT_BYTES) as u32;
 		Self(index)
 	}
 }
 
+pub(crate) fn node_checksum(top_index: ElementStoreIndex, node_bytes: &[u8]) -> u32 {
+	let mut bytes = top_index.0.to_be_bytes().to_vec();
+	bytes.extend_from_slice(node_bytes);
+	crc32(&bytes)
+}
+
 impl Add<isize> for ElementStoreIndex {
 	type Output = ElementStoreIndex;
 
@@ -7,7 +7,8 @@ use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-use crate::item_stash::element::{ELEMENT_BYTES, ElementStoreIndex};
+use crate::error::{Error, Result};
+use crate::item_stash::element::{ELEMENT_BYTES, ElementStoreIndex, node_checksum};
 use crate::key_store::field::KeyField;
 use crate::trie::{Element, ElementList, Trie, u32_from_bytes};
 
@@ -30,6 +31,17 @@ impl ElementRead {
 		self.file.read_exact_at(&mut bytes, file_index)?;
 		Ok(bytes)
 	}
+	pub fn read_node(&self, top_index: ElementStoreIndex, size: u32) -> Result<Vec<[u8; 8]>> {
+		let mut bytes = vec![0u8; (size as usize + 1) * ELEMENT_BYTES];
+		self.file.read_exact_at(&mut bytes, top_index.to_file_position())?;
+		let (node_bytes, checksum_bytes) = bytes.split_at(size as usize * ELEMENT_BYTES);
+		let checksum = [u32_from_bytes(&checksum_bytes[0..4]), u32_from_bytes(&checksum_bytes[4..8])];
+		if checksum != [node_checksum(top_index, node_bytes), size] {
+			return Err(Error::Checksum(top_index));
+		}
+		let elements = node_bytes.chunks_exact(ELEMENT_BYTES).map(|chunk| chunk.try_into().expect("element bytes")).collect();
+		Ok(elements)
+	}
 }
 
 impl Hash for ElementRead {
@@ -55,20 +67,23 @@ impl Hash for SavedElementList {
 }
 
 impl ElementList for SavedElementList {
-	fn to_elements(&self) -> Vec<Element> {
+	fn to_elements(&self) -> Result<Vec<Element>> {
 		let mut elements = Vec::new();
 		for i in 0..self.len {
-			let element = self.try_get(i).expect("get element");
+			let element = self.try_get(i)?;
 			elements.push(element.clone())
 		}
-		elements
+		Ok(elements)
 	}
 
-	fn try_get(&self, index: usize) -> io::Result<&Element> {
-		let slab = self.slab.get_or_init(|| {
-			let slab = ElementSlab::new(self.top_index, self.len as u32, self.element_read.clone()).expect("new slab");
-			Rc::new(slab)
-		});
+	fn try_get(&self, index: usize) -> Result<&Element> {
+		let slab = match self.slab.get() {
+			Some(slab) => slab,
+			None => {
+				let slab = ElementSlab::new(self.top_index, self.len as u32, self.element_read.clone())?;
+				self.slab.get_or_init(|| Rc::new(slab))
+			}
+		};
 		let element = &slab[ElementStoreIndex(self.top_index.0 + index as u32)];
 		Ok(element)
 	}
@@ -81,13 +96,14 @@ pub struct ElementSlab {
 }
 
 impl ElementSlab {
-	pub fn new(top_index: ElementStoreIndex, size: u32, element_read: Rc<ElementRead>) -> io::Result<Self> {
+	pub fn new(top_index: ElementStoreIndex, size: u32, element_read: Rc<ElementRead>) -> Result<Self> {
 		let mut elements = Vec::new();
-		{
-			let start = top_index.0;
-			let end = start + size;
-			for i in start..end {
-				let bytes = element_read.read(ElementStoreIndex(i))?;
+		if size > 0 {
+			let node = match element_read.read_node(top_index, size) {
+				Err(Error::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => return Err(Error::CorruptNode(top_index)),
+				read => read?,
+			};
+			for bytes in node {
 				let element = match Trie::parse(&bytes, element_read.clone()) {
 					Some(trie) => Element::SubTrie(trie),
 					None => {
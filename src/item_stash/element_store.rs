@@ -1,10 +1,13 @@
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
 
-use crate::item_stash::element::{ELEMENT_BYTES, ElementStoreIndex};
+use crate::error::{Error, Result};
+use crate::header::{check_file, create_file, FileKind, HEADER_BYTES};
+use crate::item_stash::element::{ELEMENT_BYTES, ElementStoreIndex, node_checksum};
 use crate::item_stash::element_read::ElementRead;
+use crate::trie::u32_from_bytes;
 
 #[derive(Debug)]
 pub struct ElementStore {
@@ -16,42 +19,52 @@ pub struct ElementStore {
 impl ElementStore {
 	pub fn append(&mut self, elements: impl AsRef<[[u32; 2]]>) -> std::io::Result<ElementStoreIndex> {
 		let start_position = self.file_length;
-		let mut write_position = start_position;
-		for element in elements.as_ref() {
-			let bytes = bytes_from_element(element);
-			match self.file.write_at(&bytes, write_position) {
-				Ok(count) => {
-					write_position += count as u64;
-				}
-				Err(error) => {
-					if write_position > start_position {
-						self.file.set_len(start_position)?;
-						return Err(error);
-					}
-				}
-			}
+		let top_index = ElementStoreIndex::from_file_position(start_position);
+		let mut bytes = elements.as_ref().iter().flat_map(bytes_from_element).collect::<Vec<_>>();
+		let checksum = [node_checksum(top_index, &bytes), elements.as_ref().len() as u32];
+		bytes.extend(bytes_from_element(&checksum));
+		if let Err(error) = self.file.write_all_at(&bytes, start_position) {
+			self.file.set_len(start_position)?;
+			return Err(error);
 		}
-		self.file_length = write_position;
-		Ok(ElementStoreIndex::from_file_position(start_position))
+		self.file_length = start_position + bytes.len() as u64;
+		Ok(top_index)
 	}
+	pub fn sync(&self) -> io::Result<()> { self.file.sync_data() }
 	pub fn len(&self) -> usize {
-		self.file_length as usize / ELEMENT_BYTES
+		ElementStoreIndex::from_file_position(self.file_length).0 as usize
 	}
 	pub fn to_element_read(&self) -> io::Result<ElementRead> { ElementRead::open(&self.store_path) }
-	pub fn open(store_path: impl AsRef<Path>) -> std::io::Result<Self> {
+	pub fn open(store_path: impl AsRef<Path>) -> Result<Self> {
 		let store_path = store_path.as_ref();
 		let file = OpenOptions::new().read(true).append(true).open(store_path)?;
+		check_file(&file, FileKind::Elements)?;
 		let file_length = file.metadata()?.len();
+		if ElementStoreIndex::from_file_position(file_length).to_file_position() != file_length {
+			return Err(Error::MisalignedStore { path: store_path.to_path_buf(), length: file_length });
+		}
 		Ok(Self { file, file_length, store_path: store_path.to_path_buf() })
 	}
-	pub fn create(store_path: impl AsRef<Path>) -> std::io::Result<()> {
-		OpenOptions::new()
-			.write(true)
-			.create(true)
-			.mode(0o600)
-			.open(&store_path)?;
-		Ok(())
+	pub fn create(store_path: impl AsRef<Path>) -> Result<()> {
+		create_file(store_path, FileKind::Elements)
+	}
+}
+
+pub(crate) fn node_records_end(file: &File) -> Result<u64> {
+	let element_count = ElementStoreIndex::from_file_position(file.metadata()?.len()).0;
+	for end in (1..=element_count).rev() {
+		let mut trailer = [0u8; ELEMENT_BYTES];
+		file.read_exact_at(&mut trailer, ElementStoreIndex(end - 1).to_file_position())?;
+		let Some(top) = (end - 1).checked_sub(u32_from_bytes(&trailer[4..8])) else {
+			continue;
+		};
+		let mut node_bytes = vec![0u8; (end - 1 - top) as usize * ELEMENT_BYTES];
+		file.read_exact_at(&mut node_bytes, ElementStoreIndex(top).to_file_position())?;
+		if node_checksum(ElementStoreIndex(top), &node_bytes) == u32_from_bytes(&trailer[0..4]) {
+			return Ok(ElementStoreIndex(end).to_file_position());
+		}
 	}
+	Ok(HEADER_BYTES)
 }
 
 fn bytes_from_element(element: &[u32; 2]) -> [u8; 8] {
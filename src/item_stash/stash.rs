@@ -2,6 +2,7 @@ use std::fs;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
+use crate::error::Result;
 use crate::item_stash::element::ElementStoreIndex;
 use crate::item_stash::element_read::ElementRead;
 use crate::item_stash::element_store::ElementStore;
@@ -16,16 +17,17 @@ impl ItemStash {
 		self.store.append(elements)
 	}
 	pub fn len(&self) -> usize { self.store.len() }
+	pub fn sync(&self) -> std::io::Result<()> { self.store.sync() }
 	pub fn to_element_read(&self) -> std::io::Result<ElementRead> { self.store.to_element_read() }
-	pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
 		let path = path.as_ref().to_path_buf();
 		let store = ElementStore::open(store_path(&path))?;
 		Ok(Self { store })
 	}
-	pub fn create(path: impl AsRef<Path>) -> std::io::Result<()> {
+	pub fn create(path: impl AsRef<Path>) -> Result<()> {
 		let stash_dir = path.as_ref();
 		if stash_dir.exists() {
-			return Err(std::io::Error::from(ErrorKind::AlreadyExists));
+			return Err(std::io::Error::from(ErrorKind::AlreadyExists).into());
 		}
 		fs::create_dir(stash_dir)?;
 		ElementStore::create(store_path(stash_dir))?;
@@ -33,6 +35,6 @@ impl ItemStash {
 	}
 }
 
-fn store_path(stash_path: impl AsRef<Path>) -> PathBuf {
+pub(crate) fn store_path(stash_path: impl AsRef<Path>) -> PathBuf {
 	stash_path.as_ref().join("elements.store")
 }
@@ -1,3 +1,8 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::FileExt;
+
+use crate::Error;
 use crate::item_stash::element::ElementStoreIndex;
 use crate::item_stash::stash::ItemStash;
 use crate::item_stash::tests::tools::named_test_dir;
@@ -10,7 +15,7 @@ fn basic() {
 		let mut stash = ItemStash::open(&test_dir).expect("open item-stash");
 		assert_eq!(0, stash.len());
 		let index = stash.append([[1, 1], [2, 2], ]).expect("append");
-		assert_eq!(2, stash.len());
+		assert_eq!(3, stash.len());
 
 		let read = stash.to_element_read().expect("read");
 		let bytes = [
@@ -21,7 +26,7 @@ fn basic() {
 	}
 	{
 		let stash = ItemStash::open(&test_dir).expect("reopen item-stash");
-		assert_eq!(2, stash.len());
+		assert_eq!(3, stash.len());
 		let position = ElementStoreIndex(0);
 		let read = stash.to_element_read().expect("read");
 		let bytes = [
@@ -32,6 +37,31 @@ fn basic() {
 	}
 }
 
+#[test]
+fn flipped_bit_fails_checksum() {
+	let test_dir = named_test_dir("item-stash-checksum");
+	ItemStash::create(&test_dir).expect("create item-stash");
+	let mut stash = ItemStash::open(&test_dir).expect("open item-stash");
+	let index = stash.append([[1, 1], [2, 2]]).expect("append");
+	let read = stash.to_element_read().expect("read");
+	assert_eq!(vec![[0, 0, 0, 1, 0, 0, 0, 1], [0, 0, 0, 2, 0, 0, 0, 2]], read.read_node(index, 2).expect("read node"));
+	assert!(matches!(read.read_node(index, 1), Err(Error::Checksum(_))));
+
+	let store = OpenOptions::new().write(true).open(test_dir.join("elements.store")).expect("open store");
+	store.write_all_at(&[0x10], (index + 1).to_file_position() + 7).expect("flip bit");
+	assert!(matches!(read.read_node(index, 2), Err(Error::Checksum(_))));
+}
+
+#[test]
+fn misaligned_store_is_refused() {
+	let test_dir = named_test_dir("item-stash-misaligned");
+	ItemStash::create(&test_dir).expect("create item-stash");
+	ItemStash::open(&test_dir).expect("open item-stash").append([[1, 1]]).expect("append");
+	let mut store = OpenOptions::new().append(true).open(test_dir.join("elements.store")).expect("open store");
+	store.write_all(&[1, 2, 3]).expect("write partial element");
+	assert!(matches!(ItemStash::open(&test_dir), Err(Error::MisalignedStore { .. })));
+}
+
 mod tools {
 	use std::{env, fs};
 	use std::path::PathBuf;
@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::header::{check_file, create_file, FileHeader, FileKind, HEADER_BYTES};
+use crate::key_store::{Key, KeyStore, ReadKey, StoredKey};
+use crate::key_store::index::{INLINE_LIMIT, KeyStoreIndex};
+
+#[cfg(test)]
+mod tests {
+	use crate::Error;
+	use crate::header::{FileHeader, FileKind, HEADER_BYTES};
+	use crate::key_store::{Key, KeyStore, ReadKey};
+	use crate::key_store::bytes::{BytesKeyStore, INTERN_CHUNK_BYTES};
+	use crate::key_store::index::{INLINE_LIMIT, KeyStoreIndex};
+	use crate::tests::ready_test_dir;
+
+	#[test]
+	fn non_utf8_keys_round_trip() {
+		let store_dir = ready_test_dir("bytes-key-store").join("store");
+		let mut store = BytesKeyStore::open_interned(&store_dir).expect("open");
+		let key = vec![0xc3, 0x28, 0x00, 0xff];
+		let index = store.write_key(&key).expect("write_key");
+		assert_eq!(key, ReadKey::<Vec<u8>>::read_key(&store, index).expect("read_key"));
+		assert_eq!(index, store.write_key(&[0xc3, 0x28, 0x00, 0xff]).expect("write array key"));
+		assert_eq!([0xc3, 0x28, 0x00, 0xff], ReadKey::<[u8; 4]>::read_key(&store, index).expect("read array key"));
+		let error = ReadKey::<[u8; 2]>::read_key(&store, index).expect_err("wrong length");
+		assert!(matches!(error, Error::KeyLength { len: 4, expected: 2 }));
+	}
+
+	#[test]
+	fn short_keys_are_inline() {
+		let store_dir = ready_test_dir("bytes-key-store-inline").join("store");
+		let mut store = BytesKeyStore::open(&store_dir).expect("open");
+		for key in [vec![], vec![0], vec![0xff, 0x00, 0x01], b"abcd".to_vec(), b"\0\0\0\0".to_vec()] {
+			let index = store.write_key(&key).expect("write_key");
+			assert!(index.is_inline(), "{:?}", key);
+			assert_eq!(key, ReadKey::<Vec<u8>>::read_key(&store, index).expect("read_key"));
+		}
+		assert_eq!(HEADER_BYTES, std::fs::metadata(&store_dir).expect("metadata").len());
+		for key in [vec![0x80, 0x61, 0x62, 0x63], b"abcde".to_vec()] {
+			let index = store.write_key(&key).expect("write_key");
+			assert!(!index.is_inline(), "{:?}", key);
+			assert_eq!(key, ReadKey::<Vec<u8>>::read_key(&store, index).expect("read_key"));
+		}
+	}
+
+	#[test]
+	fn version_one_files_store_short_keys() {
+		let store_dir = ready_test_dir("bytes-key-store-v1").join("store");
+		std::fs::write(&store_dir, FileHeader { kind: FileKind::BytesKeys, version: 1 }.to_bytes()).expect("write v1 header");
+		let mut store = BytesKeyStore::open(&store_dir).expect("open v1");
+		let index = store.write_key(&b"abc".to_vec()).expect("write_key");
+		assert_eq!(KeyStoreIndex(HEADER_BYTES as u32), index);
+		assert_eq!(b"abc".to_vec(), ReadKey::<Vec<u8>>::read_key(&store, index).expect("read_key"));
+		let high_position = KeyStoreIndex(INLINE_LIMIT | 5);
+		assert!(matches!(ReadKey::<Vec<u8>>::read_key(&store, high_position), Err(Error::Io(_))));
+	}
+
+	#[test]
+	fn interning_reads_keys_across_chunks() {
+		let store_dir = ready_test_dir("bytes-key-store-chunks").join("store");
+		let keys = (0..2000u32).map(|i| format!("{:0>100}", i).into_bytes()).collect::<Vec<_>>();
+		let indexes = {
+			let mut store = BytesKeyStore::open_interned(&store_dir).expect("open");
+			keys.iter().map(|key| store.write_key(key).expect("write_key")).collect::<Vec<_>>()
+		};
+		let length = std::fs::metadata(&store_dir).expect("metadata").len();
+		assert!(length > 2 * INTERN_CHUNK_BYTES as u64);
+		let mut store = BytesKeyStore::open_interned(&store_dir).expect("reopen");
+		for (key, index) in keys.iter().zip(indexes) {
+			assert_eq!(index, store.write_key(key).expect("write_key again"));
+		}
+		assert_eq!(length, std::fs::metadata(&store_dir).expect("metadata").len());
+	}
+
+	#[test]
+	fn shards_match_string_keys() {
+		let string_shards = (0..6).map(|depth| "ab".to_string().to_shard(depth)).collect::<Vec<_>>();
+		let bytes_shards = (0..6).map(|depth| b"ab".to_vec().to_shard(depth)).collect::<Vec<_>>();
+		let array_shards = (0..6).map(|depth| b"ab".to_shard(depth)).collect::<Vec<_>>();
+		assert_eq!(string_shards, bytes_shards);
+		assert_eq!(string_shards, array_shards);
+	}
+}
+
+const BYTES_END_SHARD: u8 = 0;
+
+pub(crate) fn bytes_to_shard(bytes: &[u8], depth: usize) -> u8 {
+	match bytes.get(depth / 2) {
+		None => BYTES_END_SHARD,
+		Some(full_byte) => {
+			let nibble = match depth.is_multiple_of(2) {
+				true => full_byte >> 4,
+				false => full_byte & 0x0f,
+			};
+			nibble + 1
+		}
+	}
+}
+
+impl Key for Vec<u8> {
+	fn to_shard(&self, depth: usize) -> u8 { bytes_to_shard(self, depth) }
+}
+
+impl<const N: usize> Key for [u8; N] {
+	fn to_shard(&self, depth: usize) -> u8 { bytes_to_shard(self, depth) }
+}
+
+impl StoredKey for Vec<u8> {
+	fn open_store(store_path: &Path, max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(BytesKeyStore::open_interned(store_path)?.with_max_key_bytes(max_key_bytes.unwrap_or(DEFAULT_MAX_KEY_BYTES))))
+	}
+}
+
+impl<const N: usize> StoredKey for [u8; N] {
+	fn open_store(store_path: &Path, max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(BytesKeyStore::open_interned(store_path)?.with_max_key_bytes(max_key_bytes.unwrap_or(DEFAULT_MAX_KEY_BYTES))))
+	}
+}
+
+pub const DEFAULT_MAX_KEY_BYTES: usize = 1 << 20;
+
+pub struct BytesKeyStore {
+	file: File,
+	size_format: SizeFormat,
+	inline_keys: bool,
+	max_key_bytes: usize,
+	interned: Option<HashMap<Vec<u8>, KeyStoreIndex>>,
+}
+
+impl BytesKeyStore {
+	pub fn open(store_path: impl AsRef<Path>) -> Result<Self> {
+		Self::open_kind(store_path, FileKind::BytesKeys)
+	}
+	pub fn open_interned(store_path: impl AsRef<Path>) -> Result<Self> {
+		Self::open(store_path)?.interned()
+	}
+	pub(crate) fn open_kind(store_path: impl AsRef<Path>, kind: FileKind) -> Result<Self> {
+		let path = store_path.as_ref();
+		if !path.exists() {
+			create_file(path, kind)?;
+		}
+		Self::from_file(OpenOptions::new().read(true).append(true).open(path)?, kind)
+	}
+	pub fn open_read_only(store_path: impl AsRef<Path>) -> Result<Self> {
+		Self::open_kind_read_only(store_path, FileKind::BytesKeys)
+	}
+	pub(crate) fn open_kind_read_only(store_path: impl AsRef<Path>, kind: FileKind) -> Result<Self> {
+		Self::from_file(File::open(store_path)?, kind)
+	}
+	fn from_file(file: File, kind: FileKind) -> Result<Self> {
+		let header = check_file(&file, kind)?;
+		let size_format = SizeFormat::from_header(kind, header.version);
+		let inline_keys = has_inline_keys(kind, header.version);
+		Ok(Self { file, size_format, inline_keys, max_key_bytes: DEFAULT_MAX_KEY_BYTES, interned: None })
+	}
+	pub(crate) fn interned(mut self) -> Result<Self> {
+		self.interned = Some(read_interned(&self.file, self.size_format)?);
+		Ok(self)
+	}
+	pub fn with_max_key_bytes(mut self, max_key_bytes: usize) -> Self {
+		self.max_key_bytes = max_key_bytes;
+		self
+	}
+	pub fn max_key_bytes(&self) -> usize {
+		self.max_key_bytes.min(self.size_format.max_size())
+	}
+	pub(crate) fn read_bytes(&self, index: KeyStoreIndex) -> Result<Vec<u8>> {
+		if self.inline_keys && index.is_inline() {
+			return Ok(inline_bytes(index.inline_payload()));
+		}
+		let (size, size_len) = {
+			let mut size_bytes = [0u8; MAX_SIZE_BYTES];
+			let read = self.file.read_at(&mut size_bytes, index.to_file_pos())?;
+			self.size_format.decode(&size_bytes[..read]).ok_or(io::Error::from(ErrorKind::UnexpectedEof))?
+		};
+		let mut buffer = vec![0u8; size];
+		self.file.read_exact_at(&mut buffer, index.to_file_pos() + size_len as u64)?;
+		Ok(buffer)
+	}
+	pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> Result<KeyStoreIndex> {
+		if let Some(payload) = inline_payload(bytes).filter(|_| self.inline_keys) {
+			return Ok(KeyStoreIndex::inline(payload));
+		}
+		if let Some(index) = self.interned.as_ref().and_then(|interned| interned.get(bytes)) {
+			return Ok(*index);
+		}
+		if bytes.len() > self.max_key_bytes() {
+			return Err(Error::KeyTooLong { len: bytes.len(), max: self.max_key_bytes() });
+		}
+		let pos = self.file.seek(SeekFrom::End(0))?;
+		let position_limit = match self.inline_keys {
+			true => INLINE_LIMIT as u64,
+			false => KEY_FIELD_LIMIT,
+		};
+		if pos >= position_limit {
+			return Err(Error::KeyStoreFull);
+		}
+		let mut record = self.size_format.encode(bytes.len());
+		record.extend_from_slice(bytes);
+		self.file.write_all(&record)?;
+		let index = KeyStoreIndex(pos as u32);
+		if let Some(interned) = self.interned.as_mut() {
+			interned.insert(bytes.to_vec(), index);
+		}
+		Ok(index)
+	}
+	pub(crate) fn sync_file(&self) -> Result<()> {
+		self.file.sync_data()?;
+		Ok(())
+	}
+}
+
+const KEY_FIELD_LIMIT: u64 = 1 << 31;
+const INLINE_ASCII: u32 = 1 << 29;
+
+fn has_inline_keys(kind: FileKind, version: u16) -> bool {
+	match kind {
+		FileKind::StringKeys => version >= 3,
+		_ => version >= 2,
+	}
+}
+const INLINE_SHORT_BYTES: usize = 3;
+
+fn inline_payload(bytes: &[u8]) -> Option<u32> {
+	if bytes.len() <= INLINE_SHORT_BYTES {
+		let packed = bytes.iter().fold(0u32, |packed, byte| (packed << 8) | *byte as u32);
+		return Some(((bytes.len() as u32) << 24) | packed);
+	}
+	if bytes.len() == 4 && bytes.is_ascii() {
+		let packed = bytes.iter().fold(0u32, |packed, byte| (packed << 7) | *byte as u32);
+		return Some(INLINE_ASCII | packed);
+	}
+	None
+}
+
+fn inline_bytes(payload: u32) -> Vec<u8> {
+	match payload & INLINE_ASCII != 0 {
+		true => (0..4).rev().map(|i| ((payload >> (7 * i)) & 0x7f) as u8).collect(),
+		false => {
+			let len = (payload >> 24) & 0x3;
+			(0..len).rev().map(|i| (payload >> (8 * i)) as u8).collect()
+		}
+	}
+}
+
+pub(crate) fn key_records_end(file: &File, header: FileHeader) -> Result<u64> {
+	let size_format = SizeFormat::from_header(header.kind, header.version);
+	let length = file.metadata()?.len();
+	let mut position = HEADER_BYTES;
+	loop {
+		let mut size_bytes = [0u8; MAX_SIZE_BYTES];
+		let read = file.read_at(&mut size_bytes, position)?;
+		let Some((size, size_len)) = size_format.decode(&size_bytes[..read]) else {
+			return Ok(position);
+		};
+		let end = position + (size_len + size) as u64;
+		if end > length {
+			return Ok(position);
+		}
+		position = end;
+	}
+}
+
+const INTERN_CHUNK_BYTES: usize = 64 * 1024;
+
+fn read_interned(file: &File, size_format: SizeFormat) -> Result<HashMap<Vec<u8>, KeyStoreIndex>> {
+	let mut interned = HashMap::new();
+	let mut chunk = vec![0u8; INTERN_CHUNK_BYTES];
+	let mut pending = Vec::new();
+	let mut pending_position = HEADER_BYTES;
+	let mut read_position = HEADER_BYTES;
+	loop {
+		let read = file.read_at(&mut chunk, read_position)?;
+		if read == 0 {
+			break;
+		}
+		read_position += read as u64;
+		pending.extend_from_slice(&chunk[..read]);
+		let mut offset = 0;
+		while let Some((size, size_len)) = size_format.decode(&pending[offset..]) {
+			let Some(bytes) = pending.get(offset + size_len..offset + size_len + size) else {
+				break;
+			};
+			interned.entry(bytes.to_vec()).or_insert(KeyStoreIndex((pending_position + offset as u64) as u32));
+			offset += size_len + size;
+		}
+		pending.drain(..offset);
+		pending_position += offset as u64;
+	}
+	Ok(interned)
+}
+
+impl ReadKey<Vec<u8>> for BytesKeyStore {
+	fn read_key(&self, index: KeyStoreIndex) -> Result<Vec<u8>> {
+		self.read_bytes(index)
+	}
+}
+
+impl KeyStore<Vec<u8>> for BytesKeyStore {
+	fn write_key(&mut self, key: &Vec<u8>) -> Result<KeyStoreIndex> {
+		self.write_bytes(key)
+	}
+	fn sync(&self) -> Result<()> {
+		self.sync_file()
+	}
+}
+
+impl<const N: usize> ReadKey<[u8; N]> for BytesKeyStore {
+	fn read_key(&self, index: KeyStoreIndex) -> Result<[u8; N]> {
+		let bytes = self.read_bytes(index)?;
+		let len = bytes.len();
+		bytes.try_into().map_err(|_| Error::KeyLength { len, expected: N })
+	}
+}
+
+impl<const N: usize> KeyStore<[u8; N]> for BytesKeyStore {
+	fn write_key(&mut self, key: &[u8; N]) -> Result<KeyStoreIndex> {
+		self.write_bytes(key)
+	}
+	fn sync(&self) -> Result<()> {
+		self.sync_file()
+	}
+}
+
+const MAX_SIZE_BYTES: usize = 5;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SizeFormat {
+	U16,
+	Varint,
+}
+
+impl SizeFormat {
+	fn from_header(kind: FileKind, version: u16) -> Self {
+		match (kind, version) {
+			(FileKind::StringKeys, 1) => SizeFormat::U16,
+			_ => SizeFormat::Varint,
+		}
+	}
+	fn max_size(self) -> usize {
+		match self {
+			SizeFormat::U16 => u16::MAX as usize,
+			SizeFormat::Varint => u32::MAX as usize,
+		}
+	}
+	fn encode(self, size: usize) -> Vec<u8> {
+		match self {
+			SizeFormat::U16 => (size as u16).to_be_bytes().to_vec(),
+			SizeFormat::Varint => {
+				let mut bytes = Vec::with_capacity(MAX_SIZE_BYTES);
+				let mut rest = size;
+				while rest >= 0x80 {
+					bytes.push((rest as u8 & 0x7f) | 0x80);
+					rest >>= 7;
+				}
+				bytes.push(rest as u8);
+				bytes
+			}
+		}
+	}
+	fn decode(self, bytes: &[u8]) -> Option<(usize, usize)> {
+		match self {
+			SizeFormat::U16 => Some((u16::from_be_bytes(bytes.get(0..2)?.try_into().ok()?) as usize, 2)),
+			SizeFormat::Varint => {
+				let mut size = 0;
+				for (i, byte) in bytes.iter().take(MAX_SIZE_BYTES).enumerate() {
+					size |= ((byte & 0x7f) as usize) << (7 * i);
+					if byte & 0x80 == 0 {
+						return Some((size, i + 1));
+					}
+				}
+				None
+			}
+		}
+	}
+}
@@ -2,10 +2,18 @@ use crate::key_store::field::KeyField;
 use crate::key_store::u32;
 use crate::trie::key_field_to_store_index;
 
+pub(crate) const INLINE_LIMIT: u32 = 0x40000000;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct KeyStoreIndex(pub(crate) u32);
 
 impl KeyStoreIndex {
+	pub(crate) fn inline(payload: u32) -> Self {
+		assert!(payload < INLINE_LIMIT);
+		Self(payload | INLINE_LIMIT)
+	}
+	pub fn is_inline(&self) -> bool { (self.0 & INLINE_LIMIT) != 0 }
+	pub(crate) fn inline_payload(&self) -> u32 { self.0 & !INLINE_LIMIT }
 	pub fn to_u32(&self) -> u32 { self.to_file_pos() as u32 }
 	pub fn to_file_pos(&self) -> u64 { self.0 as u64 }
 }
@@ -1,15 +1,17 @@
-use std::io;
+use std::path::Path;
 
+use crate::error::Result;
 use index::KeyStoreIndex;
 
 #[cfg(test)]
 mod tests {
 	use crate::key_store::{KeyStore, ReadKey};
 	use crate::key_store::u32::U32KeyStore;
+	use crate::tests::ready_test_dir;
 
 	#[test]
 	fn basic() {
-		let store = &mut U32KeyStore;
+		let store = &mut U32KeyStore::open(ready_test_dir("key-store-basic").join("keys")).expect("open");
 		let write_key = 137u32;
 		let key_store_index = store.write_key(&write_key).expect("write_key");
 		let read_key = store.read_key(key_store_index).expect("read_key");
@@ -17,8 +19,10 @@ mod tests {
 	}
 }
 
+pub mod bytes;
 pub mod field;
 pub mod index;
+pub mod ordered;
 pub mod string;
 pub mod u32;
 
@@ -26,22 +30,36 @@ pub trait Key: Eq {
 	fn to_shard(&self, depth: usize) -> u8;
 }
 
+pub trait StoredKey: Key + Sized + 'static {
+	fn open_store(store_path: &Path, max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>>;
+}
+
+pub trait OrderedKey: Key + Ord + Copy {
+	const BITS: u32;
+	fn to_ordered_bits(&self) -> u64;
+	fn from_ordered_bits(bits: u64) -> Self;
+}
+
 pub trait KeyStore<K: Key>: ReadKey<K> {
-	fn write_key(&mut self, key: &K) -> io::Result<KeyStoreIndex>;
+	fn write_key(&mut self, key: &K) -> Result<KeyStoreIndex>;
+	fn sync(&self) -> Result<()>;
 }
 
 impl<K: Key, T: KeyStore<K>> KeyStore<K> for Box<T> {
-	fn write_key(&mut self, key: &K) -> io::Result<KeyStoreIndex> {
+	fn write_key(&mut self, key: &K) -> Result<KeyStoreIndex> {
 		self.as_mut().write_key(key)
 	}
+	fn sync(&self) -> Result<()> {
+		self.as_ref().sync()
+	}
 }
 
 pub trait ReadKey<K: Key> {
-	fn read_key(&self, index: KeyStoreIndex) -> io::Result<K>;
+	fn read_key(&self, index: KeyStoreIndex) -> Result<K>;
 }
 
 impl<K: Key, T: ReadKey<K>> ReadKey<K> for Box<T> {
-	fn read_key(&self, index: KeyStoreIndex) -> io::Result<K> {
+	fn read_key(&self, index: KeyStoreIndex) -> Result<K> {
 		self.as_ref().read_key(index)
 	}
 }
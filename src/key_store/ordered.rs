@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::header::{check_file, create_file, FileKind, HEADER_BYTES};
+use crate::key_store::{Key, KeyStore, OrderedKey, ReadKey, StoredKey};
+use crate::key_store::index::{INLINE_LIMIT, KeyStoreIndex};
+use crate::trie::ordered_key_shard;
+
+const SIGN_BIT_64: u64 = 1 << 63;
+const SIGN_BIT_32: u64 = 1 << 31;
+const INLINE_SIGNED_MIN: i64 = -((INLINE_LIMIT / 2) as i64);
+const INLINE_SIGNED_LIMIT: i64 = (INLINE_LIMIT / 2) as i64;
+
+pub trait FixedKey: OrderedKey + Hash + 'static {
+	const FILE_KIND: FileKind;
+	fn to_inline(&self) -> Option<u32>;
+	fn from_inline(payload: u32) -> Self;
+}
+
+impl Key for u64 {
+	fn to_shard(&self, depth: usize) -> u8 { ordered_key_shard(self.to_ordered_bits(), Self::BITS, depth) }
+}
+
+impl OrderedKey for u64 {
+	const BITS: u32 = 64;
+	fn to_ordered_bits(&self) -> u64 { *self }
+	fn from_ordered_bits(bits: u64) -> Self { bits }
+}
+
+impl StoredKey for u64 {
+	fn open_store(store_path: &Path, _max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(OrderedKeyStore::<u64>::open(store_path)?))
+	}
+}
+
+impl FixedKey for u64 {
+	const FILE_KIND: FileKind = FileKind::U64Keys;
+	fn to_inline(&self) -> Option<u32> { (*self < INLINE_LIMIT as u64).then_some(*self as u32) }
+	fn from_inline(payload: u32) -> Self { payload as u64 }
+}
+
+impl Key for i64 {
+	fn to_shard(&self, depth: usize) -> u8 { ordered_key_shard(self.to_ordered_bits(), Self::BITS, depth) }
+}
+
+impl OrderedKey for i64 {
+	const BITS: u32 = 64;
+	fn to_ordered_bits(&self) -> u64 { (*self as u64) ^ SIGN_BIT_64 }
+	fn from_ordered_bits(bits: u64) -> Self { (bits ^ SIGN_BIT_64) as i64 }
+}
+
+impl StoredKey for i64 {
+	fn open_store(store_path: &Path, _max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(OrderedKeyStore::<i64>::open(store_path)?))
+	}
+}
+
+impl FixedKey for i64 {
+	const FILE_KIND: FileKind = FileKind::I64Keys;
+	fn to_inline(&self) -> Option<u32> {
+		(INLINE_SIGNED_MIN..INLINE_SIGNED_LIMIT).contains(self).then_some(*self as u32 & (INLINE_LIMIT - 1))
+	}
+	fn from_inline(payload: u32) -> Self { i32::from_inline(payload) as i64 }
+}
+
+impl Key for i32 {
+	fn to_shard(&self, depth: usize) -> u8 { ordered_key_shard(self.to_ordered_bits(), Self::BITS, depth) }
+}
+
+impl OrderedKey for i32 {
+	const BITS: u32 = 32;
+	fn to_ordered_bits(&self) -> u64 { (*self as u32 as u64) ^ SIGN_BIT_32 }
+	fn from_ordered_bits(bits: u64) -> Self { (bits ^ SIGN_BIT_32) as u32 as i32 }
+}
+
+impl StoredKey for i32 {
+	fn open_store(store_path: &Path, _max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(OrderedKeyStore::<i32>::open(store_path)?))
+	}
+}
+
+impl FixedKey for i32 {
+	const FILE_KIND: FileKind = FileKind::I32Keys;
+	fn to_inline(&self) -> Option<u32> { (*self as i64).to_inline() }
+	fn from_inline(payload: u32) -> Self { ((payload << 2) as i32) >> 2 }
+}
+
+pub struct OrderedKeyStore<K: FixedKey> {
+	file: File,
+	record_count: u64,
+	stored: HashMap<K, KeyStoreIndex>,
+	phantom: PhantomData<K>,
+}
+
+impl<K: FixedKey> OrderedKeyStore<K> {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		if !path.exists() {
+			create_file(path, K::FILE_KIND)?;
+		}
+		Self::from_file(OpenOptions::new().read(true).write(true).open(path)?)
+	}
+	pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+		Self::from_file(File::open(path)?)
+	}
+	fn from_file(file: File) -> Result<Self> {
+		check_file(&file, K::FILE_KIND)?;
+		let record_count = (file.metadata()?.len() - HEADER_BYTES) / record_bytes::<K>();
+		let mut stored = HashMap::new();
+		for record_index in 0..record_count {
+			stored.insert(read_record(&file, record_index)?, KeyStoreIndex::from(record_index as u32));
+		}
+		Ok(Self { file, record_count, stored, phantom: PhantomData })
+	}
+}
+
+pub(crate) fn record_bytes<K: FixedKey>() -> u64 {
+	(K::BITS / 8) as u64
+}
+
+fn record_position<K: FixedKey>(record_index: u64) -> u64 {
+	HEADER_BYTES + record_index * record_bytes::<K>()
+}
+
+fn read_record<K: FixedKey>(file: &File, record_index: u64) -> Result<K> {
+	let mut bytes = [0u8; 8];
+	let record = &mut bytes[8 - record_bytes::<K>() as usize..];
+	file.read_exact_at(record, record_position::<K>(record_index))?;
+	Ok(K::from_ordered_bits(u64::from_be_bytes(bytes)))
+}
+
+impl<K: FixedKey> ReadKey<K> for OrderedKeyStore<K> {
+	fn read_key(&self, index: KeyStoreIndex) -> Result<K> {
+		if index.is_inline() {
+			return Ok(K::from_inline(index.inline_payload()));
+		}
+		read_record(&self.file, index.to_file_pos())
+	}
+}
+
+impl<K: FixedKey> KeyStore<K> for OrderedKeyStore<K> {
+	fn write_key(&mut self, key: &K) -> Result<KeyStoreIndex> {
+		if let Some(payload) = key.to_inline() {
+			return Ok(KeyStoreIndex::inline(payload));
+		}
+		if let Some(index) = self.stored.get(key) {
+			return Ok(*index);
+		}
+		if self.record_count >= INLINE_LIMIT as u64 {
+			return Err(Error::KeyStoreFull);
+		}
+		let bytes = key.to_ordered_bits().to_be_bytes();
+		self.file.write_all_at(&bytes[8 - record_bytes::<K>() as usize..], record_position::<K>(self.record_count))?;
+		let index = KeyStoreIndex::from(self.record_count as u32);
+		self.record_count += 1;
+		self.stored.insert(*key, index);
+		Ok(index)
+	}
+	fn sync(&self) -> Result<()> {
+		self.file.sync_data()?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::header::HEADER_BYTES;
+	use crate::key_store::{Key, KeyStore, OrderedKey, ReadKey};
+	use crate::key_store::ordered::OrderedKeyStore;
+	use crate::tests::ready_test_dir;
+
+	#[test]
+	fn keys_round_trip_inline_and_stored() {
+		let store_path = ready_test_dir("ordered-key-store").join("keys");
+		{
+			let mut store = OrderedKeyStore::<i64>::open(&store_path).expect("open");
+			for key in [0, -1, 1 << 29, -(1 << 29), i64::MIN, i64::MAX] {
+				let index = store.write_key(&key).expect("write_key");
+				assert_eq!((-(1 << 29)..1 << 29).contains(&key), index.is_inline(), "{}", key);
+				assert_eq!(key, store.read_key(index).expect("read_key"));
+			}
+			store.write_key(&i64::MAX).expect("write again");
+		}
+		let mut store = OrderedKeyStore::<i64>::open(&store_path).expect("reopen");
+		let index = store.write_key(&i64::MIN).expect("write");
+		assert_eq!(i64::MIN, store.read_key(index).expect("read"));
+		assert_eq!(HEADER_BYTES + 3 * 8, std::fs::metadata(&store_path).expect("metadata").len());
+	}
+
+	#[test]
+	fn signed_shards_follow_numeric_order() {
+		let keys = [i32::MIN, -70_000, -1, 0, 1, 70_000, i32::MAX];
+		let shards = keys.iter().map(|key| (0..7).map(|depth| key.to_shard(depth)).collect::<Vec<_>>()).collect::<Vec<_>>();
+		assert!(shards.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", shards);
+		assert_eq!(-5, i64::from_ordered_bits((-5i64).to_ordered_bits()));
+		assert_eq!(vec![0x8, 0, 0], (0..3).map(|depth| 0i64.to_shard(depth)).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn writes_after_a_torn_tail_stay_aligned() {
+		let store_path = ready_test_dir("ordered-key-store-torn").join("keys");
+		OrderedKeyStore::<u64>::open(&store_path).expect("open").write_key(&u64::MAX).expect("write");
+		let mut file = std::fs::OpenOptions::new().append(true).open(&store_path).expect("open raw");
+		std::io::Write::write_all(&mut file, &[0xab, 0xcd, 0xef]).expect("write torn tail");
+		let mut store = OrderedKeyStore::<u64>::open(&store_path).expect("reopen");
+		let index = store.write_key(&(u64::MAX - 1)).expect("write after tail");
+		let store = OrderedKeyStore::<u64>::open(&store_path).expect("reopen again");
+		assert_eq!(u64::MAX - 1, store.read_key(index).expect("read"));
+		assert_eq!(HEADER_BYTES + 2 * 8, std::fs::metadata(&store_path).expect("metadata").len());
+	}
+}
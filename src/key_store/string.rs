@@ -1,15 +1,21 @@
-use std::fs::{File, OpenOptions};
-use std::io;
-use std::io::{Seek, SeekFrom, Write};
-use std::os::unix::fs::{FileExt, OpenOptionsExt};
 use std::path::Path;
 
-use crate::key_store::{Key, KeyStore, ReadKey};
+use crate::error::{Error, Result};
+use crate::header::FileKind;
+use crate::key_store::{Key, KeyStore, ReadKey, StoredKey};
+use crate::key_store::bytes::{bytes_to_shard, BytesKeyStore, DEFAULT_MAX_KEY_BYTES};
 use crate::key_store::index::KeyStoreIndex;
 
 #[cfg(test)]
 mod tests {
-	use crate::key_store::{KeyStore, ReadKey};
+	use std::fs::{File, OpenOptions};
+	use std::io::Write;
+
+	use crate::Error;
+	use crate::header::{FileHeader, FileKind, HEADER_BYTES};
+	use crate::key_store::{Key, KeyStore, ReadKey};
+	use crate::key_store::index::KeyStoreIndex;
+	use crate::key_store::bytes::DEFAULT_MAX_KEY_BYTES;
 	use crate::key_store::string::StringKeyStore;
 	use crate::tests::ready_test_dir;
 
@@ -23,67 +29,117 @@ mod tests {
 		let read_key = store.read_key(index).expect("read_key");
 		assert_eq!(key, read_key);
 	}
-}
 
-impl Key for String {
-	fn to_shard(&self, depth: usize) -> u8 {
-		let full_byte = self.as_bytes()[depth / 2];
-		match (depth % 2) == 0 {
-			true => full_byte >> 4,
-			false => full_byte & 0x0f,
-		}
+	#[test]
+	fn interned_store_writes_each_key_once() {
+		let store_dir = ready_test_dir("string-key-store-interned").join("store");
+		let (hello, length) = {
+			let mut store = StringKeyStore::open_interned(&store_dir).expect("open");
+			let hello = store.write_key(&"hello".to_string()).expect("write_key");
+			let world = store.write_key(&"world".to_string()).expect("write_key");
+			assert_eq!(hello, store.write_key(&"hello".to_string()).expect("write_key again"));
+			assert_ne!(hello, world);
+			(hello, std::fs::metadata(&store_dir).expect("metadata").len())
+		};
+		let mut store = StringKeyStore::open_interned(&store_dir).expect("reopen");
+		assert_eq!(hello, store.write_key(&"hello".to_string()).expect("write_key after reopen"));
+		assert_eq!(length, std::fs::metadata(&store_dir).expect("metadata").len());
+		let mut plain = StringKeyStore::open(&store_dir).expect("open plain");
+		assert_ne!(hello, plain.write_key(&"hello".to_string()).expect("write_key plain"));
+	}
+
+	#[test]
+	fn invalid_utf8_and_long_keys_are_errors() {
+		let test_dir = ready_test_dir("string-key-store-errors");
+		let store_dir = test_dir.join("store");
+		let mut store = StringKeyStore::open(&store_dir).expect("open").with_max_key_bytes(16);
+		let error = store.write_key(&"x".repeat(17)).expect_err("too long");
+		assert!(matches!(error, Error::KeyTooLong { len: 17, max: 16 }));
+		OpenOptions::new().append(true).open(&store_dir).expect("raw open").write_all(&[4, 0xc3, 0x28, 0xc3, 0x28]).expect("raw write");
+		let error = store.read_key(KeyStoreIndex(HEADER_BYTES as u32)).expect_err("bad utf8");
+		assert!(matches!(error, Error::InvalidUtf8Key(_)));
+	}
+
+	#[test]
+	fn keys_longer_than_u16_round_trip() {
+		let store_dir = ready_test_dir("string-key-store-long").join("store");
+		let mut store = StringKeyStore::open(&store_dir).expect("open");
+		let long_key = "y".repeat(u16::MAX as usize + 10);
+		let long_index = store.write_key(&long_key).expect("write long key");
+		let short_index = store.write_key(&"short".to_string()).expect("write short key");
+		assert_eq!(long_key, store.read_key(long_index).expect("read long key"));
+		assert_eq!("short", store.read_key(short_index).expect("read short key"));
+		let error = store.write_key(&"z".repeat(DEFAULT_MAX_KEY_BYTES + 1)).expect_err("too long");
+		assert!(matches!(error, Error::KeyTooLong { max: DEFAULT_MAX_KEY_BYTES, .. }));
+	}
+
+	#[test]
+	fn version_one_files_keep_u16_sizes() {
+		let store_dir = ready_test_dir("string-key-store-v1").join("store");
+		let mut file = File::create(&store_dir).expect("create");
+		file.write_all(&FileHeader { kind: FileKind::StringKeys, version: 1 }.to_bytes()).expect("write header");
+		file.write_all(&[0, 5, b'o', b'l', b'd', b'e', b'r']).expect("write v1 key");
+		drop(file);
+		let mut store = StringKeyStore::open_interned(&store_dir).expect("open v1");
+		let old = KeyStoreIndex(HEADER_BYTES as u32);
+		assert_eq!("older", store.read_key(old).expect("read v1 key"));
+		assert_eq!(old, store.write_key(&"older".to_string()).expect("interned v1 key"));
+		let new = store.write_key(&"newer".to_string()).expect("write v1 key");
+		assert!(!store.write_key(&"ab".to_string()).expect("write short v1 key").is_inline());
+		assert_eq!("newer", store.read_key(new).expect("read new key"));
+		assert_eq!(HEADER_BYTES + 18, std::fs::metadata(&store_dir).expect("metadata").len());
+		let error = store.write_key(&"x".repeat(u16::MAX as usize + 1)).expect_err("too long for v1");
+		assert!(matches!(error, Error::KeyTooLong { max: 65535, .. }));
+	}
+
+	#[test]
+	fn shards_end_after_last_nibble() {
+		let key = "a".to_string();
+		let shards = (0..4).map(|depth| key.to_shard(depth)).collect::<Vec<_>>();
+		assert_eq!(vec![0x7, 0x2, 0, 0], shards);
+		assert_eq!(0, String::new().to_shard(0));
 	}
 }
 
+impl Key for String {
+	fn to_shard(&self, depth: usize) -> u8 { bytes_to_shard(self.as_bytes(), depth) }
+}
 
-pub struct StringKeyStore {
-	file: File,
+impl StoredKey for String {
+	fn open_store(store_path: &Path, max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(StringKeyStore::open_interned(store_path)?.with_max_key_bytes(max_key_bytes.unwrap_or(DEFAULT_MAX_KEY_BYTES))))
+	}
 }
 
+pub struct StringKeyStore(BytesKeyStore);
+
 impl StringKeyStore {
-	pub fn open(store_path: impl AsRef<Path>) -> io::Result<Self> {
-		let path = store_path.as_ref();
-		let file = if !path.exists() {
-			OpenOptions::new().mode(0o600).create(true).read(true).append(true).open(path)?
-		} else {
-			OpenOptions::new().read(true).append(true).open(path)?
-		};
-		Ok(Self { file })
+	pub fn open(store_path: impl AsRef<Path>) -> Result<Self> {
+		BytesKeyStore::open_kind(store_path, FileKind::StringKeys).map(Self)
+	}
+	pub fn open_interned(store_path: impl AsRef<Path>) -> Result<Self> {
+		BytesKeyStore::open_kind(store_path, FileKind::StringKeys)?.interned().map(Self)
+	}
+	pub fn open_read_only(store_path: impl AsRef<Path>) -> Result<Self> {
+		BytesKeyStore::open_kind_read_only(store_path, FileKind::StringKeys).map(Self)
 	}
+	pub fn with_max_key_bytes(self, max_key_bytes: usize) -> Self {
+		Self(self.0.with_max_key_bytes(max_key_bytes))
+	}
+	pub fn max_key_bytes(&self) -> usize { self.0.max_key_bytes() }
 }
 
 impl ReadKey<String> for StringKeyStore {
-	fn read_key(&self, index: KeyStoreIndex) -> io::Result<String> {
-		let size = {
-			let mut size_bytes = [0u8; 2];
-			self.file.read_exact_at(&mut size_bytes, index.to_file_pos())?;
-			decode_size(size_bytes)
-		};
-		let string = {
-			let mut buffer = vec![0u8; size];
-			self.file.read_exact_at(&mut buffer, index.to_file_pos() + 2)?;
-			String::from_utf8(buffer).expect("utf8 in buffer")
-		};
-		Ok(string)
+	fn read_key(&self, index: KeyStoreIndex) -> Result<String> {
+		String::from_utf8(self.0.read_bytes(index)?).map_err(Error::InvalidUtf8Key)
 	}
 }
 
-fn decode_size(bytes: [u8; 2]) -> usize {
-	u16::from_be_bytes(bytes) as usize
-}
-
 impl KeyStore<String> for StringKeyStore {
-	fn write_key(&mut self, key: &String) -> io::Result<KeyStoreIndex> {
-		let pos = self.file.seek(SeekFrom::End(0))?;
-		let bytes = key.as_bytes();
-		let size_bytes = encode_size(bytes.len());
-		self.file.write_all(&size_bytes)?;
-		self.file.write_all(bytes)?;
-		let index = KeyStoreIndex(pos as u32);
-		Ok(index)
+	fn write_key(&mut self, key: &String) -> Result<KeyStoreIndex> {
+		self.0.write_bytes(key.as_bytes())
+	}
+	fn sync(&self) -> Result<()> {
+		self.0.sync_file()
 	}
-}
-
-fn encode_size(size: usize) -> [u8; 2] {
-	(size as u16).to_be_bytes()
 }
@@ -1,27 +1,84 @@
-use std::io;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::FileExt;
 use std::path::Path;
-use crate::key_store::{Key, KeyStore, ReadKey, u32};
-use crate::key_store::index::KeyStoreIndex;
-use crate::trie::u32_key_byte;
 
-pub struct U32KeyStore;
+use crate::error::{Error, Result};
+use crate::header::{check_file, create_file, FileKind, HEADER_BYTES};
+use crate::key_store::{Key, KeyStore, OrderedKey, ReadKey, StoredKey};
+use crate::key_store::index::{INLINE_LIMIT, KeyStoreIndex};
+use crate::trie::{u32_from_bytes, u32_key_byte, u32_to_bytes};
+
+pub(crate) const RECORD_BYTES: u64 = 4;
+
+pub struct U32KeyStore {
+	file: File,
+	record_count: u64,
+	stored: HashMap<u32, KeyStoreIndex>,
+}
 
 impl U32KeyStore {
-	pub fn create(_path: impl AsRef<Path>) -> io::Result<()> { Ok(()) }
-	pub fn open(_path: impl AsRef<Path>) -> io::Result<Self> {
-		Ok(U32KeyStore)
+	pub fn create(path: impl AsRef<Path>) -> Result<()> {
+		create_file(path, FileKind::U32Keys)
+	}
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		if !path.exists() {
+			Self::create(path)?;
+		}
+		Self::from_file(OpenOptions::new().read(true).write(true).open(path)?)
+	}
+	pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+		Self::from_file(File::open(path)?)
+	}
+	fn from_file(file: File) -> Result<Self> {
+		check_file(&file, FileKind::U32Keys)?;
+		let record_count = (file.metadata()?.len() - HEADER_BYTES) / RECORD_BYTES;
+		let mut stored = HashMap::new();
+		for record_index in 0..record_count {
+			let mut bytes = [0u8; RECORD_BYTES as usize];
+			file.read_exact_at(&mut bytes, record_position(record_index))?;
+			stored.insert(u32_from_bytes(&bytes), KeyStoreIndex::from(record_index as u32));
+		}
+		Ok(U32KeyStore { file, record_count, stored })
 	}
 }
 
+fn record_position(record_index: u64) -> u64 {
+	HEADER_BYTES + record_index * RECORD_BYTES
+}
+
 impl ReadKey<u32> for U32KeyStore {
-	fn read_key(&self, index: KeyStoreIndex) -> io::Result<u32> {
-		Ok(index.to_u32())
+	fn read_key(&self, index: KeyStoreIndex) -> Result<u32> {
+		if index.is_inline() {
+			return Ok(index.inline_payload());
+		}
+		let mut bytes = [0u8; RECORD_BYTES as usize];
+		self.file.read_exact_at(&mut bytes, record_position(index.to_file_pos()))?;
+		Ok(u32_from_bytes(&bytes))
 	}
 }
 
 impl KeyStore<u32> for U32KeyStore {
-	fn write_key(&mut self, key: &u32) -> io::Result<KeyStoreIndex> {
-		Ok(KeyStoreIndex::from(*key))
+	fn write_key(&mut self, key: &u32) -> Result<KeyStoreIndex> {
+		if *key < INLINE_LIMIT {
+			return Ok(KeyStoreIndex::inline(*key));
+		}
+		if let Some(index) = self.stored.get(key) {
+			return Ok(*index);
+		}
+		if self.record_count >= INLINE_LIMIT as u64 {
+			return Err(Error::KeyStoreFull);
+		}
+		self.file.write_all_at(&u32_to_bytes(*key), record_position(self.record_count))?;
+		let index = KeyStoreIndex::from(self.record_count as u32);
+		self.record_count += 1;
+		self.stored.insert(*key, index);
+		Ok(index)
+	}
+	fn sync(&self) -> Result<()> {
+		self.file.sync_data()?;
+		Ok(())
 	}
 }
 
@@ -31,3 +88,64 @@ impl Key for u32 {
 	}
 }
 
+impl StoredKey for u32 {
+	fn open_store(store_path: &Path, _max_key_bytes: Option<usize>) -> Result<Box<dyn KeyStore<Self>>> {
+		Ok(Box::new(U32KeyStore::open(store_path)?))
+	}
+}
+
+impl OrderedKey for u32 {
+	const BITS: u32 = 32;
+	fn to_ordered_bits(&self) -> u64 { *self as u64 }
+	fn from_ordered_bits(bits: u64) -> Self { bits as u32 }
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::Error;
+	use crate::header::{FileHeader, FileKind, HEADER_BYTES};
+	use crate::key_store::{KeyStore, ReadKey};
+	use crate::key_store::index::KeyStoreIndex;
+	use crate::key_store::u32::U32KeyStore;
+	use crate::tests::ready_test_dir;
+
+	#[test]
+	fn high_keys_are_stored_once() {
+		let store_path = ready_test_dir("u32-key-store").join("keys");
+		{
+			let mut store = U32KeyStore::open(&store_path).expect("open");
+			let low = store.write_key(&7).expect("write low");
+			let high = store.write_key(&u32::MAX).expect("write high");
+			assert!(low.is_inline());
+			assert!(!high.is_inline());
+			assert_eq!(high, store.write_key(&u32::MAX).expect("write high again"));
+			assert_eq!(u32::MAX, store.read_key(high).expect("read high"));
+		}
+		let mut store = U32KeyStore::open(&store_path).expect("reopen");
+		let high = store.write_key(&u32::MAX).expect("write high");
+		assert_eq!(KeyStoreIndex::from(0), high);
+		assert_eq!(HEADER_BYTES + 4, std::fs::metadata(&store_path).expect("metadata").len());
+	}
+
+	#[test]
+	fn writes_after_a_torn_tail_stay_aligned() {
+		let store_path = ready_test_dir("u32-key-store-torn").join("keys");
+		U32KeyStore::open(&store_path).expect("open").write_key(&u32::MAX).expect("write high");
+		let mut file = std::fs::OpenOptions::new().append(true).open(&store_path).expect("open raw");
+		std::io::Write::write_all(&mut file, &[0xab, 0xcd]).expect("write torn tail");
+		let mut store = U32KeyStore::open(&store_path).expect("reopen");
+		let index = store.write_key(&(u32::MAX - 1)).expect("write after tail");
+		assert_eq!(KeyStoreIndex::from(1), index);
+		let store = U32KeyStore::open(&store_path).expect("reopen again");
+		assert_eq!(u32::MAX - 1, store.read_key(index).expect("read"));
+		assert_eq!(HEADER_BYTES + 8, std::fs::metadata(&store_path).expect("metadata").len());
+	}
+
+	#[test]
+	fn version_one_files_are_refused() {
+		let store_path = ready_test_dir("u32-key-store-v1").join("keys");
+		std::fs::write(&store_path, FileHeader { kind: FileKind::U32Keys, version: 1 }.to_bytes()).expect("write v1 header");
+		let error = U32KeyStore::open(&store_path).err().expect("v1 is refused");
+		assert!(matches!(error, Error::UnsupportedVersion { kind: FileKind::U32Keys, version: 1 }));
+	}
+}
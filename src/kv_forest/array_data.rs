@@ -1,7 +1,7 @@
 use std::fmt::Debug;
-use std::io;
 use std::ops::Index;
 
+use crate::error::Result;
 use crate::item_stash::element::ElementStoreIndex;
 use crate::item_stash::element_read::SavedElementList;
 use crate::trie::{DirectElementList, Element, ElementList};
@@ -13,21 +13,28 @@ pub enum ElementData {
 }
 
 impl ElementData {
-	pub fn insert(&self, index: usize, element: Element) -> Self {
+	pub fn insert(&self, index: usize, element: Element) -> Result<Self> {
 		let new_element_list = match self {
-			ElementData::Direct(direct) => direct.insert(index, element),
-			ElementData::Indirect(indirect) => indirect.insert(index, element),
+			ElementData::Direct(direct) => direct.insert(index, element)?,
+			ElementData::Indirect(indirect) => indirect.insert(index, element)?,
 		};
-		Self::Direct(new_element_list)
+		Ok(Self::Direct(new_element_list))
 	}
-	pub fn replace(&self, index: usize, element: Element) -> Self {
+	pub fn replace(&self, index: usize, element: Element) -> Result<Self> {
 		let new_element_list = match self {
-			ElementData::Direct(direct) => direct.replace(index, element),
-			ElementData::Indirect(indirect) => indirect.replace(index, element),
+			ElementData::Direct(direct) => direct.replace(index, element)?,
+			ElementData::Indirect(indirect) => indirect.replace(index, element)?,
 		};
-		Self::Direct(new_element_list)
+		Ok(Self::Direct(new_element_list))
 	}
-	pub fn try_get(&self, index: usize) -> io::Result<&Element> {
+	pub fn remove(&self, index: usize) -> Result<Self> {
+		let new_element_list = match self {
+			ElementData::Direct(direct) => direct.remove(index)?,
+			ElementData::Indirect(indirect) => indirect.remove(index)?,
+		};
+		Ok(Self::Direct(new_element_list))
+	}
+	pub fn try_get(&self, index: usize) -> Result<&Element> {
 		match self {
 			ElementData::Direct(direct) => direct.try_get(index),
 			ElementData::Indirect(indirect) => indirect.try_get(index)
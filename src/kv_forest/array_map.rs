@@ -20,6 +20,12 @@ mod tests {
 			array_maps
 		);
 	}
+
+	#[test]
+	fn viewing_indices_to_keys() {
+		let map = ElementMap::just_key(0).include_key(7).include_key(31);
+		assert_eq!([0, 7, 31], array::from_fn(|i| map.to_key(i)));
+	}
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -38,6 +44,10 @@ impl ElementMap {
 		let new_flags = self.0 | key_flag(key);
 		Self(new_flags)
 	}
+	pub fn exclude_key(&self, key: u8) -> Self {
+		let new_flags = self.0 & !key_flag(key);
+		Self(new_flags)
+	}
 	pub fn has_key(&self, key: u8) -> bool {
 		let key_flag = key_flag(key);
 		let masked = self.0 & key_flag;
@@ -49,6 +59,13 @@ impl ElementMap {
 			false => None,
 		}
 	}
+	pub fn to_key(&self, viewing_index: usize) -> u8 {
+		let mut flags = self.0;
+		for _ in 0..viewing_index {
+			flags &= flags - 1;
+		}
+		flags.trailing_zeros() as u8
+	}
 	pub fn to_insertion_index(&self, key: u8) -> usize {
 		let masked_map = self.0 & count_ones_mask(key);
 		masked_map.count_ones() as usize
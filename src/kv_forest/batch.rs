@@ -0,0 +1,36 @@
+use crate::error::Result;
+use crate::key_store::Key;
+use crate::kv_forest::{KvForest, RootIndex};
+use crate::trie::Trie;
+use crate::value_store::Value;
+
+#[must_use]
+pub struct KvBatch<'a, K: Key, V: Value> {
+	forest: &'a mut KvForest<K, V>,
+	root_index: RootIndex,
+	trie: Trie,
+}
+
+impl<'a, K: Key, V: Value> KvBatch<'a, K, V> {
+	pub(crate) fn new(forest: &'a mut KvForest<K, V>, root_index: RootIndex) -> Result<Self> {
+		let trie = forest.trie(root_index)?;
+		Ok(Self { forest, root_index, trie })
+	}
+	pub fn push(&mut self, insert_key: K, value: V) -> Result<()> {
+		let value = self.forest.value_store.write_value(&value)?;
+		self.trie = self.trie.push(insert_key, value.to_u32(), &mut self.forest.key_store)?;
+		Ok(())
+	}
+	pub fn remove(&mut self, remove_key: &K) -> Result<()> {
+		self.trie = self.trie.remove(remove_key, &self.forest.key_store)?;
+		Ok(())
+	}
+	pub fn commit(self) -> Result<RootIndex> {
+		let root_index = match self.trie.is_data_direct() {
+			true => RootIndex(self.forest.save(self.trie)?),
+			false => self.root_index,
+		};
+		self.forest.commit(root_index)?;
+		Ok(root_index)
+	}
+}
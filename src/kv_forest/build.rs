@@ -0,0 +1,85 @@
+use std::cmp::Ordering;
+
+use crate::error::{Error, Result};
+use crate::key_store::{Key, KeyStore};
+use crate::key_store::field::KeyField;
+use crate::kv_forest::{KvForest, RootIndex};
+use crate::kv_forest::array_map::ElementMap;
+use crate::trie::u32_from_stash_index;
+use crate::value_store::Value;
+
+struct OpenNode {
+	key_byte: u8,
+	map: ElementMap,
+	elements: Vec<[u32; 2]>,
+}
+
+impl OpenNode {
+	fn new(key_byte: u8) -> Self {
+		Self { key_byte, map: ElementMap::empty(), elements: Vec::new() }
+	}
+	fn push(&mut self, key_byte: u8, element: [u32; 2]) {
+		self.map = self.map.include_key(key_byte);
+		self.elements.push(element);
+	}
+}
+
+impl<K: Key, V: Value> KvForest<K, V> {
+	pub fn build_from_sorted(&mut self, entries: impl IntoIterator<Item=(K, V)>) -> Result<RootIndex> {
+		let mut entries = entries.into_iter().peekable();
+		let mut open_nodes = vec![OpenNode::new(0)];
+		let mut previous_key: Option<K> = None;
+		while let Some((key, value)) = entries.next() {
+			let previous_depth = match &previous_key {
+				None => 0,
+				Some(previous_key) => shared_shard_depth(previous_key, &key)?,
+			};
+			let next_depth = match entries.peek() {
+				None => 0,
+				Some((next_key, _)) => shared_shard_depth(&key, next_key)?,
+			};
+			while open_nodes.len() > previous_depth + 1 {
+				self.close_node(&mut open_nodes)?;
+			}
+			let key_depth = previous_depth.max(next_depth);
+			while open_nodes.len() <= key_depth {
+				let key_byte = key.to_shard(open_nodes.len() - 1);
+				open_nodes.push(OpenNode::new(key_byte));
+			}
+			let key_field = KeyField::from(self.key_store.write_key(&key)?);
+			let value_field = self.value_store.write_value(&value)?;
+			let node = open_nodes.last_mut().expect("open node");
+			node.push(key.to_shard(key_depth), [key_field.to_u32(), value_field.to_u32()]);
+			previous_key = Some(key);
+		}
+		while open_nodes.len() > 1 {
+			self.close_node(&mut open_nodes)?;
+		}
+		let root_node = open_nodes.pop().expect("root node");
+		let top_index = self.element_stash.append(root_node.elements)?;
+		let root_index = self.element_stash.append([[u32_from_stash_index(top_index.0), root_node.map.0]])?;
+		Ok(RootIndex(root_index))
+	}
+
+	fn close_node(&mut self, open_nodes: &mut Vec<OpenNode>) -> Result<()> {
+		let node = open_nodes.pop().expect("open node");
+		let stash_index = self.element_stash.append(node.elements)?;
+		let parent = open_nodes.last_mut().expect("parent node");
+		parent.push(node.key_byte, [u32_from_stash_index(stash_index.0), node.map.0]);
+		Ok(())
+	}
+}
+
+fn shared_shard_depth<K: Key>(key: &K, next_key: &K) -> Result<usize> {
+	if key == next_key {
+		return Err(Error::UnsortedKeys);
+	}
+	let mut depth = 0;
+	loop {
+		match key.to_shard(depth).cmp(&next_key.to_shard(depth)) {
+			Ordering::Less => return Ok(depth),
+			Ordering::Equal => depth += 1,
+			Ordering::Greater => return Err(Error::UnsortedKeys),
+		}
+	}
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use crate::checksum::crc32;
+use crate::error::Result;
+use crate::header::{check_file, create_file, FileHeader, FileKind, HEADER_BYTES};
+use crate::item_stash::element::ElementStoreIndex;
+use crate::kv_forest::RootIndex;
+use crate::kv_forest::recovery::StoreLengths;
+use crate::trie::{u32_from_bytes, u32_to_bytes};
+
+const V2_CHECKED_BYTES: usize = 28;
+const FIELD_BYTES: usize = 40;
+const NAME_LENGTH_BYTES: usize = 4;
+const CRC_BYTES: usize = 4;
+const NO_PARENT: u32 = u32::MAX;
+const NO_REF: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct CommitRecord {
+	pub root_index: RootIndex,
+	pub parent: Option<RootIndex>,
+	pub timestamp: u64,
+	pub lengths: StoreLengths,
+	pub ref_name: Option<String>,
+}
+
+impl CommitRecord {
+	fn to_bytes(&self) -> Vec<u8> {
+		let name = self.ref_name.as_deref().map(str::as_bytes);
+		let mut bytes = Vec::with_capacity(FIELD_BYTES + NAME_LENGTH_BYTES + name.map_or(0, <[u8]>::len) + CRC_BYTES);
+		bytes.extend_from_slice(&u32_to_bytes(self.root_index.0.0));
+		bytes.extend_from_slice(&u32_to_bytes(self.parent.map(|parent| parent.0.0).unwrap_or(NO_PARENT)));
+		bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+		bytes.extend_from_slice(&self.lengths.elements.to_be_bytes());
+		bytes.extend_from_slice(&self.lengths.keys.to_be_bytes());
+		bytes.extend_from_slice(&self.lengths.values.to_be_bytes());
+		match name {
+			None => bytes.extend_from_slice(&u32_to_bytes(NO_REF)),
+			Some(name) => {
+				bytes.extend_from_slice(&u32_to_bytes(name.len() as u32));
+				bytes.extend_from_slice(name);
+			}
+		}
+		let crc = crc32(&bytes);
+		bytes.extend_from_slice(&u32_to_bytes(crc));
+		bytes
+	}
+	fn parse(version: u16, bytes: &[u8]) -> Option<(Self, usize)> {
+		match version {
+			2 => {
+				let checked = bytes.get(0..V2_CHECKED_BYTES)?;
+				let crc = bytes.get(V2_CHECKED_BYTES..V2_CHECKED_BYTES + CRC_BYTES)?;
+				let lengths = StoreLengths {
+					elements: u64_from_bytes(&checked[4..12]),
+					keys: u64_from_bytes(&checked[12..20]),
+					values: u64_from_bytes(&checked[20..28]),
+				};
+				let record = Self {
+					root_index: RootIndex(ElementStoreIndex(u32_from_bytes(&checked[0..4]))),
+					parent: None,
+					timestamp: 0,
+					lengths,
+					ref_name: None,
+				};
+				(crc32(checked) == u32_from_bytes(crc)).then_some((record, V2_CHECKED_BYTES + CRC_BYTES))
+			}
+			3 => {
+				let checked = bytes.get(0..FIELD_BYTES)?;
+				let crc = bytes.get(FIELD_BYTES..FIELD_BYTES + CRC_BYTES)?;
+				(crc32(checked) == u32_from_bytes(crc)).then(|| (Self::parse_fields(checked, None), FIELD_BYTES + CRC_BYTES))
+			}
+			_ => {
+				let name_start = FIELD_BYTES + NAME_LENGTH_BYTES;
+				let (name_end, ref_name) = match u32_from_bytes(bytes.get(FIELD_BYTES..name_start)?) {
+					NO_REF => (name_start, None),
+					length => {
+						let name_end = name_start.checked_add(length as usize)?;
+						(name_end, Some(String::from_utf8(bytes.get(name_start..name_end)?.to_vec()).ok()?))
+					}
+				};
+				let crc = bytes.get(name_end..name_end + CRC_BYTES)?;
+				(crc32(&bytes[0..name_end]) == u32_from_bytes(crc)).then(|| (Self::parse_fields(bytes, ref_name), name_end + CRC_BYTES))
+			}
+		}
+	}
+	fn parse_fields(bytes: &[u8], ref_name: Option<String>) -> Self {
+		let root_index = RootIndex(ElementStoreIndex(u32_from_bytes(&bytes[0..4])));
+		let parent = match u32_from_bytes(&bytes[4..8]) {
+			NO_PARENT => None,
+			parent => Some(RootIndex(ElementStoreIndex(parent))),
+		};
+		let timestamp = u64_from_bytes(&bytes[8..16]);
+		let lengths = StoreLengths {
+			elements: u64_from_bytes(&bytes[16..24]),
+			keys: u64_from_bytes(&bytes[24..32]),
+			values: u64_from_bytes(&bytes[32..40]),
+		};
+		Self { root_index, parent, timestamp, lengths, ref_name }
+	}
+}
+
+fn u64_from_bytes(bytes: &[u8]) -> u64 {
+	u64::from_be_bytes(bytes.try_into().expect("eight bytes"))
+}
+
+pub(crate) struct CommitLog {
+	file: File,
+	records: Vec<CommitRecord>,
+	end: u64,
+}
+
+impl CommitLog {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		if !path.exists() {
+			create_file(path, FileKind::Commits)?;
+		}
+		let file = OpenOptions::new().read(true).write(true).open(path)?;
+		let (header, records, end) = read_log(&file)?;
+		if header.version < FileKind::Commits.version() {
+			rewrite(path, &records)?;
+			return Self::open(path);
+		}
+		file.set_len(end)?;
+		Ok(Self { file, records, end })
+	}
+	pub fn latest(&self) -> Option<&CommitRecord> { self.records.last() }
+	pub fn latest_commit(&self) -> Option<&CommitRecord> {
+		self.records.iter().rev().find(|record| record.ref_name.is_none())
+	}
+	pub fn records(&self) -> &[CommitRecord] { &self.records }
+	pub fn append(&mut self, record: CommitRecord) -> Result<()> {
+		let bytes = record.to_bytes();
+		self.file.write_all_at(&bytes, self.end)?;
+		self.file.sync_data()?;
+		self.end += bytes.len() as u64;
+		self.records.push(record);
+		Ok(())
+	}
+}
+
+pub(crate) fn read_records(file: &File) -> Result<Vec<CommitRecord>> {
+	Ok(read_log(file)?.1)
+}
+
+fn read_log(file: &File) -> Result<(FileHeader, Vec<CommitRecord>, u64)> {
+	let header = check_file(file, FileKind::Commits)?;
+	let mut bytes = vec![0u8; (file.metadata()?.len() - HEADER_BYTES) as usize];
+	file.read_exact_at(&mut bytes, HEADER_BYTES)?;
+	let mut records = Vec::new();
+	let mut position = 0;
+	while let Some((record, record_bytes)) = CommitRecord::parse(header.version, &bytes[position..]) {
+		records.push(record);
+		position += record_bytes;
+	}
+	Ok((header, records, HEADER_BYTES + position as u64))
+}
+
+fn rewrite(path: &Path, records: &[CommitRecord]) -> Result<()> {
+	let mut bytes = FileHeader::current(FileKind::Commits).to_bytes().to_vec();
+	for record in records {
+		bytes.extend_from_slice(&record.to_bytes());
+	}
+	let tmp_path = path.with_extension("tmp");
+	{
+		let tmp = File::create(&tmp_path)?;
+		tmp.write_all_at(&bytes, 0)?;
+		tmp.sync_all()?;
+	}
+	fs::rename(&tmp_path, path)?;
+	if let Some(dir) = path.parent() {
+		File::open(dir)?.sync_all()?;
+	}
+	Ok(())
+}
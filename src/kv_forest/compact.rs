@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::item_stash::element::ElementStoreIndex;
+use crate::item_stash::stash::store_path;
+use crate::key_store::{Key, KeyStore, ReadKey};
+use crate::key_store::field::KeyField;
+use crate::key_store::index::KeyStoreIndex;
+use crate::kv_forest::{commit_log_path, element_stash_path, key_store_path, KvForest, refs_path, RootIndex, value_store_path};
+use crate::kv_forest::array_map::ElementMap;
+use crate::trie::{u32_from_bytes, u32_from_stash_index, u32_is_stash_index};
+use crate::value_store::{ReadValue, Value, ValueField, ValueStore};
+
+impl<K: Key, V: Value> KvForest<K, V> {
+	/// Copies `live_roots`, every ref target and the latest root into fresh stores and returns where each landed.
+	///
+	/// Commit log records whose root is not copied are dropped, so [`history`](Self::history) and
+	/// [`rollback`](Self::rollback) only reach the roots in the returned map afterwards. A kept record
+	/// whose parent was dropped loses its parent.
+	pub fn compact(&mut self, live_roots: &[RootIndex]) -> Result<HashMap<RootIndex, RootIndex>> {
+		let forest_path = self.forest_path.clone();
+		let pending_path = compact_pending_path(&forest_path);
+		if pending_path.exists() {
+			fs::remove_dir_all(&pending_path)?;
+		}
+		let remapped = {
+			let mut target = Self::open_or_create_with_keys_store_builder(&pending_path, self.build_key_store, self.options)?;
+			let mut compaction = Compaction {
+				source: self,
+				target: &mut target,
+				nodes: HashMap::new(),
+				keys: HashMap::new(),
+				values: HashMap::new(),
+			};
+			let mut remapped = HashMap::new();
+			for root_index in live_roots.iter().copied().chain(self.refs.roots()).chain([self.latest_root()]) {
+				if let Entry::Vacant(entry) = remapped.entry(root_index) {
+					entry.insert(compaction.copy_root(root_index)?);
+				}
+			}
+			for record in self.commit_log.records() {
+				if let Some(root_index) = remapped.get(&record.root_index) {
+					let parent = record.parent.and_then(|parent| remapped.get(&parent).copied());
+					target.commit_with_parent(*root_index, parent, record.timestamp, record.ref_name.as_deref())?;
+				}
+			}
+			for (name, root_index) in self.refs.list() {
+				target.refs.update(&name, Some(remapped[&root_index]))?;
+			}
+			remapped
+		};
+		fs::rename(&pending_path, compact_ready_path(&forest_path))?;
+		File::open(&forest_path)?.sync_all()?;
+		finish_compaction(&forest_path)?;
+		*self = Self::open_or_create_with_keys_store_builder(&forest_path, self.build_key_store, self.options)?;
+		Ok(remapped)
+	}
+}
+
+pub(crate) fn finish_compaction(forest_path: &Path) -> Result<()> {
+	let pending_path = compact_pending_path(forest_path);
+	if pending_path.exists() {
+		fs::remove_dir_all(&pending_path)?;
+	}
+	let ready_path = compact_ready_path(forest_path);
+	if !ready_path.exists() {
+		return Ok(());
+	}
+	let moves = [
+		(store_path(element_stash_path(&ready_path)), store_path(element_stash_path(forest_path))),
+		(key_store_path(&ready_path), key_store_path(forest_path)),
+		(value_store_path(&ready_path), value_store_path(forest_path)),
+		(commit_log_path(&ready_path), commit_log_path(forest_path)),
+		(refs_path(&ready_path), refs_path(forest_path)),
+	];
+	for (from, to) in moves {
+		if from.exists() {
+			fs::rename(from, to)?;
+		}
+	}
+	File::open(forest_path)?.sync_all()?;
+	fs::remove_dir_all(&ready_path)?;
+	Ok(())
+}
+
+fn compact_pending_path(forest_path: &Path) -> PathBuf {
+	forest_path.join("compact.pending")
+}
+
+fn compact_ready_path(forest_path: &Path) -> PathBuf {
+	forest_path.join("compact.ready")
+}
+
+struct Compaction<'a, K: Key, V: Value> {
+	source: &'a KvForest<K, V>,
+	target: &'a mut KvForest<K, V>,
+	nodes: HashMap<u32, u32>,
+	keys: HashMap<u32, u32>,
+	values: HashMap<u32, u32>,
+}
+
+impl<K: Key, V: Value> Compaction<'_, K, V> {
+	fn copy_root(&mut self, root_index: RootIndex) -> Result<RootIndex> {
+		let [top, map] = self.read_node(root_index.0.0, 1)?[0];
+		if map == 0 {
+			return Ok(RootIndex(ElementStoreIndex(0)));
+		}
+		let new_top = self.copy_node(top, ElementMap(map))?;
+		let new_root = self.target.element_stash.append([[u32_from_stash_index(new_top), map]])?;
+		Ok(RootIndex(new_root))
+	}
+	fn copy_node(&mut self, top: u32, map: ElementMap) -> Result<u32> {
+		if let Some(new_top) = self.nodes.get(&top) {
+			return Ok(*new_top);
+		}
+		let mut elements = Vec::new();
+		for [left, right] in self.read_node(top, map.count_ones())? {
+			elements.push(match u32_is_stash_index(left) {
+				true => [u32_from_stash_index(self.copy_node(left, ElementMap(right))?), right],
+				false => [self.copy_key(left)?, self.copy_value(right)?],
+			});
+		}
+		let new_top = self.target.element_stash.append(elements)?.0;
+		self.nodes.insert(top, new_top);
+		Ok(new_top)
+	}
+	fn copy_key(&mut self, key_field: u32) -> Result<u32> {
+		if let Some(new_key_field) = self.keys.get(&key_field) {
+			return Ok(*new_key_field);
+		}
+		let key = self.source.key_store.read_key(KeyStoreIndex::from(&KeyField(key_field)))?;
+		let new_key_field = KeyField::from(self.target.key_store.write_key(&key)?).to_u32();
+		self.keys.insert(key_field, new_key_field);
+		Ok(new_key_field)
+	}
+	fn copy_value(&mut self, value_field: u32) -> Result<u32> {
+		if let Some(new_value_field) = self.values.get(&value_field) {
+			return Ok(*new_value_field);
+		}
+		let value = self.source.value_store.read_value(ValueField::from(value_field))?;
+		let new_value_field = self.target.value_store.write_value(&value)?.to_u32();
+		self.values.insert(value_field, new_value_field);
+		Ok(new_value_field)
+	}
+	fn read_node(&self, top: u32, count: u32) -> Result<Vec<[u32; 2]>> {
+		let node = self.source.element_read.read_node(ElementStoreIndex(top), count)?;
+		Ok(node.iter().map(|bytes| [u32_from_bytes(&bytes[0..4]), u32_from_bytes(&bytes[4..8])]).collect())
+	}
+}
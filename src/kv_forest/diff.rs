@@ -0,0 +1,69 @@
+use crate::error::Result;
+use crate::key_store::Key;
+use crate::key_store::field::KeyField;
+use crate::kv_forest::{KvForest, RootIndex};
+use crate::trie::{Element, Trie};
+use crate::value_store::Value;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Diff<K, V> {
+	Added(K, V),
+	Removed(K, V),
+	Changed(K, V, V),
+}
+
+impl<K: Key, V: Value + PartialEq> KvForest<K, V> {
+	pub fn diff(&self, old_root: RootIndex, new_root: RootIndex) -> Result<Vec<Diff<K, V>>> {
+		let mut diffs = Vec::new();
+		self.diff_tries(&self.trie(old_root)?, &self.trie(new_root)?, &mut diffs)?;
+		Ok(diffs)
+	}
+	fn diff_tries(&self, old: &Trie, new: &Trie, diffs: &mut Vec<Diff<K, V>>) -> Result<()> {
+		let old_index = old.elements.to_stash_index();
+		if old_index.is_some() && old_index == new.elements.to_stash_index() && old.map == new.map {
+			return Ok(());
+		}
+		for shard in (0..32).filter(|shard| old.map.has_key(*shard) || new.map.has_key(*shard)) {
+			let old_element = old.map.to_viewing_index(shard).map(|index| old.elements.try_get(index)).transpose()?;
+			let new_element = new.map.to_viewing_index(shard).map(|index| new.elements.try_get(index)).transpose()?;
+			match (old_element, new_element) {
+				(Some(Element::SubTrie(old_trie)), Some(Element::SubTrie(new_trie))) => {
+					self.diff_tries(old_trie, new_trie, diffs)?;
+				}
+				(old_element, new_element) => {
+					let mut old_fields = old_element.map(element_fields).transpose()?.unwrap_or_default();
+					let mut new_fields = new_element.map(element_fields).transpose()?.unwrap_or_default();
+					old_fields.retain(|old_field| match new_fields.iter().position(|new_field| new_field == old_field) {
+						None => true,
+						Some(position) => {
+							new_fields.remove(position);
+							false
+						}
+					});
+					let old_entries = old_fields.into_iter().map(|field| self.read_entry(field)).collect::<Result<Vec<_>>>()?;
+					let mut new_entries = new_fields.into_iter().map(|field| self.read_entry(field)).collect::<Result<Vec<_>>>()?;
+					for (key, old_value) in old_entries {
+						match new_entries.iter().position(|(new_key, _)| *new_key == key) {
+							None => diffs.push(Diff::Removed(key, old_value)),
+							Some(position) => {
+								let (_, new_value) = new_entries.remove(position);
+								if old_value != new_value {
+									diffs.push(Diff::Changed(key, old_value, new_value));
+								}
+							}
+						}
+					}
+					diffs.extend(new_entries.into_iter().map(|(key, value)| Diff::Added(key, value)));
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+fn element_fields(element: &Element) -> Result<Vec<(KeyField, u32)>> {
+	match element {
+		Element::KeyValue { key, value } => Ok(vec![(*key, *value)]),
+		Element::SubTrie(trie) => trie.entries().collect(),
+	}
+}
@@ -0,0 +1,41 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::key_store::Key;
+use crate::kv_forest::{KvForest, now_millis, RootIndex};
+use crate::value_store::Value;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct HistoryEntry {
+	pub root_index: RootIndex,
+	pub parent: Option<RootIndex>,
+	pub committed_at: SystemTime,
+}
+
+impl<K: Key, V: Value> KvForest<K, V> {
+	pub fn history(&self, name: &str) -> Result<Vec<HistoryEntry>> {
+		let mut records = self.commit_log.records().iter().rev().filter(|record| record.ref_name.as_deref() == Some(name));
+		let mut entries = Vec::new();
+		let mut next = Some(self.get_ref(name).ok_or_else(|| Error::UnknownRef(name.to_string()))?);
+		while let Some(root_index) = next {
+			let Some(record) = records.find(|record| record.root_index == root_index) else {
+				break;
+			};
+			entries.push(HistoryEntry {
+				root_index,
+				parent: record.parent,
+				committed_at: UNIX_EPOCH + Duration::from_millis(record.timestamp),
+			});
+			next = record.parent;
+		}
+		Ok(entries)
+	}
+	pub fn rollback(&mut self, name: &str, to_root: RootIndex) -> Result<()> {
+		if !self.history(name)?.iter().any(|entry| entry.root_index == to_root) {
+			return Err(Error::NotInHistory { name: name.to_string(), root_index: to_root });
+		}
+		let current = self.refs.get(name);
+		self.commit_with_parent(to_root, current, now_millis(), Some(name))?;
+		self.refs.update(name, Some(to_root))
+	}
+}
@@ -0,0 +1,165 @@
+use crate::error::Result;
+use crate::key_store::{Key, ReadKey};
+use crate::key_store::index::KeyStoreIndex;
+use crate::kv_forest::{KvForest, RootIndex};
+use crate::trie::{Element, Trie};
+use crate::value_store::{Value, ValueField};
+
+type Change<K> = (K, Option<u32>);
+
+impl<K: Key, V: Value + PartialEq> KvForest<K, V> {
+	pub fn merge(
+		&mut self,
+		base: RootIndex,
+		ours: RootIndex,
+		theirs: RootIndex,
+		mut resolve: impl FnMut(&K, Option<&V>, Option<&V>, Option<&V>) -> Option<V>,
+	) -> Result<RootIndex> {
+		let (base_trie, our_trie, their_trie) = (self.trie(base)?, self.trie(ours)?, self.trie(theirs)?);
+		if same_trie(&our_trie, &base_trie) {
+			return Ok(theirs);
+		}
+		if same_trie(&their_trie, &base_trie) || same_trie(&our_trie, &their_trie) {
+			return Ok(ours);
+		}
+		let mut changes = Vec::new();
+		let mut merged = self.merge_tries(&base_trie, &our_trie, &their_trie, &mut resolve, &mut changes)?;
+		for (key, value) in changes {
+			merged = match value {
+				None => merged.remove(&key, &self.key_store)?,
+				Some(value) => merged.push(key, value, &mut self.key_store)?,
+			};
+		}
+		if !merged.is_data_direct() {
+			return Ok(ours);
+		}
+		Ok(RootIndex(self.save(merged)?))
+	}
+	fn merge_tries<R: FnMut(&K, Option<&V>, Option<&V>, Option<&V>) -> Option<V>>(
+		&mut self,
+		base: &Trie,
+		ours: &Trie,
+		theirs: &Trie,
+		resolve: &mut R,
+		changes: &mut Vec<Change<K>>,
+	) -> Result<Trie> {
+		if same_trie(ours, base) {
+			return Ok(theirs.clone());
+		}
+		if same_trie(theirs, base) || same_trie(ours, theirs) {
+			return Ok(ours.clone());
+		}
+		let mut merged = ours.clone();
+		for shard in (0..32).filter(|shard| base.map.has_key(*shard) || ours.map.has_key(*shard) || theirs.map.has_key(*shard)) {
+			let base_element = element_at(base, shard)?;
+			let our_element = element_at(ours, shard)?;
+			let their_element = element_at(theirs, shard)?;
+			let replacement = match (base_element, our_element, their_element) {
+				(None | Some(Element::SubTrie(_)), Some(Element::SubTrie(our_trie)), Some(Element::SubTrie(their_trie))) => {
+					let empty = Trie::new();
+					let base_trie = match base_element {
+						Some(Element::SubTrie(base_trie)) => base_trie,
+						_ => &empty,
+					};
+					let merged_trie = self.merge_tries(base_trie, our_trie, their_trie, resolve, changes)?;
+					if same_trie(&merged_trie, our_trie) {
+						continue;
+					}
+					match merged_trie.to_lifted_element()? {
+						Some(element) => Some(element),
+						None if merged_trie.elements.len() == 0 => None,
+						None => Some(Element::SubTrie(merged_trie)),
+					}
+				}
+				_ if same_element(their_element, base_element) || same_element(our_element, their_element) => continue,
+				_ if same_element(our_element, base_element) => their_element.cloned(),
+				_ => {
+					self.merge_entries(base_element, our_element, their_element, resolve, changes)?;
+					continue;
+				}
+			};
+			merged = match replacement {
+				None => merged.remove_element(shard)?,
+				Some(element) => merged.insert_or_replace_element(shard, element)?,
+			};
+		}
+		Ok(merged)
+	}
+	fn merge_entries<R: FnMut(&K, Option<&V>, Option<&V>, Option<&V>) -> Option<V>>(
+		&mut self,
+		base: Option<&Element>,
+		ours: Option<&Element>,
+		theirs: Option<&Element>,
+		resolve: &mut R,
+		changes: &mut Vec<Change<K>>,
+	) -> Result<()> {
+		let base_entries = self.element_fields(base)?;
+		let our_entries = self.element_fields(ours)?;
+		let mut their_entries = self.element_fields(theirs)?;
+		let mut their_changes = Vec::new();
+		for (key, _, base_value) in base_entries {
+			match their_entries.iter().position(|(their_key, _, _)| *their_key == key) {
+				None => their_changes.push((key, Some(base_value), None)),
+				Some(position) => {
+					let (_, their_field, their_value) = their_entries.remove(position);
+					if their_value != base_value {
+						their_changes.push((key, Some(base_value), Some((their_field, their_value))));
+					}
+				}
+			}
+		}
+		their_changes.extend(their_entries.into_iter().map(|(key, field, value)| (key, None, Some((field, value)))));
+		for (key, base_value, their_entry) in their_changes {
+			let our_value = our_entries.iter().find(|(our_key, _, _)| *our_key == key).map(|(_, _, value)| value);
+			let their_value = their_entry.as_ref().map(|(_, value)| value);
+			let their_field = their_entry.as_ref().map(|(field, _)| *field);
+			let field = if our_value == base_value.as_ref() {
+				their_field
+			} else if our_value == their_value {
+				continue;
+			} else {
+				match resolve(&key, base_value.as_ref(), our_value, their_value) {
+					value if value.as_ref() == our_value => continue,
+					value if value.as_ref() == their_value => their_field,
+					None => None,
+					Some(value) => Some(self.value_store.write_value(&value)?.to_u32()),
+				}
+			};
+			changes.push((key, field));
+		}
+		Ok(())
+	}
+	fn element_fields(&self, element: Option<&Element>) -> Result<Vec<(K, u32, V)>> {
+		let fields = match element {
+			None => Vec::new(),
+			Some(Element::KeyValue { key, value }) => vec![(*key, *value)],
+			Some(Element::SubTrie(trie)) => trie.entries().collect::<Result<Vec<_>>>()?,
+		};
+		fields.into_iter().map(|(key, value)| {
+			let key = self.key_store.read_key(KeyStoreIndex::from(&key))?;
+			Ok((key, value, self.value_store.read_value(ValueField::from(value))?))
+		}).collect()
+	}
+}
+
+fn element_at(trie: &Trie, shard: u8) -> Result<Option<&Element>> {
+	trie.map.to_viewing_index(shard).map(|index| trie.elements.try_get(index)).transpose()
+}
+
+fn same_trie(left: &Trie, right: &Trie) -> bool {
+	let left_index = left.elements.to_stash_index();
+	let same_elements = (left_index.is_some() && left_index == right.elements.to_stash_index())
+		|| (left.elements.len() == 0 && right.elements.len() == 0);
+	same_elements && left.map == right.map
+}
+
+fn same_element(left: Option<&Element>, right: Option<&Element>) -> bool {
+	match (left, right) {
+		(None, None) => true,
+		(Some(Element::KeyValue { key: left_key, value: left_value }), Some(Element::KeyValue { key: right_key, value: right_value })) => {
+			left_key == right_key && left_value == right_value
+		}
+		(Some(Element::SubTrie(left)), Some(Element::SubTrie(right))) => same_trie(left, right),
+		_ => false,
+	}
+}
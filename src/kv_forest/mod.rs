@@ -3,113 +3,282 @@ use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::io::ErrorKind;
+use std::ops::{Bound, RangeBounds};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::error::{Error, Result};
 use crate::item_stash::element::ElementStoreIndex;
 use crate::item_stash::element_read::{ElementRead, SavedElementList};
 use crate::item_stash::stash::ItemStash;
-use crate::key_store::{Key, KeyStore, ReadKey};
+use crate::key_store::{Key, KeyStore, OrderedKey, ReadKey, StoredKey};
+use crate::key_store::field::KeyField;
 use crate::key_store::index::KeyStoreIndex;
-use crate::key_store::string::StringKeyStore;
-use crate::key_store::u32::U32KeyStore;
 use crate::kv_forest::array_data::ElementData;
-use crate::trie::{Element, Trie, u32_from_stash_index};
+use crate::kv_forest::batch::KvBatch;
+use crate::kv_forest::commit_log::{CommitLog, CommitRecord};
+use crate::kv_forest::compact::finish_compaction;
+use crate::kv_forest::recovery::{recover, Recovery, StoreLengths};
+use crate::kv_forest::refs::RefTable;
+use crate::trie::{Element, ordered_shard_bounds, Trie, u32_from_stash_index};
+use crate::value_store::{Value, ValueField, ValueStore};
+use crate::value_store::inline::InlineValue;
 
 #[cfg(test)]
 mod tests;
 pub mod array_map;
 pub mod array_data;
+pub mod batch;
+mod build;
+mod compact;
+pub mod diff;
+pub mod history;
+mod merge;
+pub(crate) mod commit_log;
+pub mod recovery;
+mod refs;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[must_use]
-pub struct RootIndex(ElementStoreIndex);
-
-impl RootIndex {
-	pub(crate) fn to_u32(&self) -> u32 { self.0.0 }
-}
+pub struct RootIndex(pub(crate) ElementStoreIndex);
 
 impl From<u32> for RootIndex {
 	fn from(value: u32) -> Self { RootIndex(ElementStoreIndex(value)) }
 }
 
+impl InlineValue for RootIndex {
+	fn to_value_field(&self) -> ValueField { ValueField::from(self.0.0) }
+	fn from_value_field(field: ValueField) -> Self { RootIndex::from(field.to_u32()) }
+}
+
 struct SizedKeyStore<K: Key>(Box<dyn KeyStore<K>>);
 
 impl<K: Key> ReadKey<K> for SizedKeyStore<K> {
-	fn read_key(&self, index: KeyStoreIndex) -> io::Result<K> { self.0.read_key(index) }
+	fn read_key(&self, index: KeyStoreIndex) -> Result<K> { self.0.read_key(index) }
 }
 
 impl<K: Key> KeyStore<K> for SizedKeyStore<K> {
-	fn write_key(&mut self, key: &K) -> io::Result<KeyStoreIndex> { self.0.write_key(key) }
+	fn write_key(&mut self, key: &K) -> Result<KeyStoreIndex> { self.0.write_key(key) }
+	fn sync(&self) -> Result<()> { self.0.sync() }
+}
+
+type BuildKeyStore<K> = fn(&Path, Option<usize>) -> Result<SizedKeyStore<K>>;
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ForestOptions {
+	pub keep_uncommitted: bool,
+	pub max_key_bytes: Option<usize>,
 }
 
-pub struct KvForest<K: Key> {
+pub struct KvForest<K: Key, V: Value = u32> {
 	element_stash: ItemStash,
 	element_read: Rc<ElementRead>,
 	key_store: SizedKeyStore<K>,
+	value_store: Box<dyn ValueStore<V>>,
+	commit_log: CommitLog,
+	forest_path: PathBuf,
+	recovery: Recovery,
+	build_key_store: BuildKeyStore<K>,
+	options: ForestOptions,
+	refs: RefTable,
 }
 
-impl KvForest<u32> {
-	pub fn open(forest_path: impl AsRef<Path>) -> io::Result<Self> {
+impl<K: StoredKey, V: Value> KvForest<K, V> {
+	pub fn open(forest_path: impl AsRef<Path>) -> Result<Self> {
+		Self::open_with_options(forest_path, ForestOptions::default())
+	}
+	pub fn open_with_options(forest_path: impl AsRef<Path>, options: ForestOptions) -> Result<Self> {
 		let forest = Self::open_or_create_with_keys_store_builder(
 			forest_path,
-			|path| U32KeyStore::open(path).map(|ks| SizedKeyStore(Box::new(ks) as Box<dyn KeyStore<u32>>)),
+			|path, max_key_bytes| K::open_store(path, max_key_bytes).map(SizedKeyStore),
+			options,
 		)?;
 		Ok(forest)
 	}
 }
 
-impl KvForest<String> {
-	pub fn open(forest_path: impl AsRef<Path>) -> io::Result<Self> {
-		let forest = Self::open_or_create_with_keys_store_builder(
-			forest_path,
-			|path| StringKeyStore::open(path).map(|ks| SizedKeyStore(Box::new(ks) as Box<dyn KeyStore<String>>)),
-		)?;
-		Ok(forest)
+impl<K: OrderedKey, V: Value> KvForest<K, V> {
+	pub fn range(&self, root_index: RootIndex, range: impl RangeBounds<K>) -> Result<impl Iterator<Item=Result<(K, V)>> + '_> {
+		let bounds = ordered_inclusive_bounds(&range);
+		let trie = bounds.map(|_| self.trie(root_index)).transpose()?;
+		let (start, end) = bounds.unwrap_or((1, 0));
+		let iter = trie
+			.into_iter()
+			.flat_map(move |trie| trie.entries_within(move |shards| {
+				let (low, high) = ordered_shard_bounds(shards, K::BITS);
+				low <= end && high >= start
+			}))
+			.map(|entry| self.read_entry(entry?))
+			.filter(move |entry| !matches!(entry, Ok((key, _)) if key.to_ordered_bits() < start))
+			.take_while(move |entry| !matches!(entry, Ok((key, _)) if key.to_ordered_bits() > end));
+		Ok(iter)
+	}
+	pub fn min(&self, root_index: RootIndex) -> Result<Option<(K, V)>> {
+		self.range(root_index, ..)?.next().transpose()
+	}
+	pub fn max(&self, root_index: RootIndex) -> Result<Option<(K, V)>> {
+		let mut trie = self.trie(root_index)?;
+		loop {
+			let len = trie.elements.len();
+			if len == 0 {
+				return Ok(None);
+			}
+			let sub_trie = match trie.elements.try_get(len - 1)? {
+				Element::KeyValue { key, value } => {
+					return self.read_entry((*key, *value)).map(Some);
+				}
+				Element::SubTrie(sub_trie) => sub_trie.clone(),
+			};
+			trie = sub_trie;
+		}
+	}
+	pub fn first_after(&self, root_index: RootIndex, key: K) -> Result<Option<(K, V)>> {
+		self.range(root_index, (Bound::Excluded(key), Bound::Unbounded))?.next().transpose()
+	}
+}
+
+fn ordered_inclusive_bounds<K: OrderedKey>(range: &impl RangeBounds<K>) -> Option<(u64, u64)> {
+	let max_bits = u64::MAX >> (64 - K::BITS);
+	let start = match range.start_bound() {
+		Bound::Included(start) => start.to_ordered_bits(),
+		Bound::Excluded(start) => Some(start.to_ordered_bits()).filter(|bits| *bits < max_bits)? + 1,
+		Bound::Unbounded => 0,
+	};
+	let end = match range.end_bound() {
+		Bound::Included(end) => end.to_ordered_bits(),
+		Bound::Excluded(end) => end.to_ordered_bits().checked_sub(1)?,
+		Bound::Unbounded => max_bits,
+	};
+	(start <= end).then_some((start, end))
+}
+
+impl<V: Value> KvForest<String, V> {
+	pub fn scan_prefix(&self, root_index: RootIndex, prefix: &str) -> Result<impl Iterator<Item=Result<(String, V)>> + '_> {
+		let prefix = prefix.to_string();
+		let prefix_depth = prefix.len() * 2;
+		let mut trie = self.trie(root_index)?;
+		let mut depth = 0;
+		while depth < prefix_depth {
+			let Some(viewing_index) = trie.map.to_viewing_index(prefix.to_shard(depth)) else {
+				break;
+			};
+			let sub_trie = match trie.elements.try_get(viewing_index)? {
+				Element::KeyValue { .. } => break,
+				Element::SubTrie(sub_trie) => sub_trie.clone(),
+			};
+			trie = sub_trie;
+			depth += 1;
+		}
+		let prefix_shards = (depth..prefix_depth).map(|depth| prefix.to_shard(depth)).collect::<Vec<_>>();
+		let iter = trie
+			.entries_within(move |shards| {
+				shards.iter().zip(&prefix_shards).all(|(shard, prefix_shard)| shard == prefix_shard)
+			})
+			.map(|entry| self.read_entry(entry?))
+			.filter(move |entry| !matches!(entry, Ok((key, _)) if !key.starts_with(&prefix)));
+		Ok(iter)
 	}
 }
 
-impl<K: Key> KvForest<K> {
-	pub fn create(path: impl AsRef<Path>) -> io::Result<()> {
+impl<K: Key, V: Value> KvForest<K, V> {
+	pub fn create(path: impl AsRef<Path>) -> Result<()> {
 		let forest_path = path.as_ref();
 		if forest_path.exists() {
-			return Err(io::Error::from(ErrorKind::AlreadyExists));
+			return Err(io::Error::from(ErrorKind::AlreadyExists).into());
 		}
 		fs::create_dir(forest_path)?;
 		ItemStash::create(element_stash_path(forest_path))?;
 		ItemStash::open(element_stash_path(forest_path))?.append([[0u32, 0u32]])?;
-		U32KeyStore::create(key_store_path(forest_path))?;
 		Ok(())
 	}
-	fn open_or_create_with_keys_store_builder(forest_path: impl AsRef<Path>, build_keys_store: impl Fn(&Path) -> io::Result<SizedKeyStore<K>>) -> io::Result<Self> {
+	fn open_or_create_with_keys_store_builder(forest_path: impl AsRef<Path>, build_key_store: BuildKeyStore<K>, options: ForestOptions) -> Result<Self> {
 		if !forest_path.as_ref().exists() {
 			Self::create(&forest_path)?;
 		}
+		finish_compaction(forest_path.as_ref())?;
+		let commit_log = CommitLog::open(commit_log_path(&forest_path))?;
+		let recovery = recover(forest_path.as_ref(), &commit_log, !options.keep_uncommitted)?;
 		let (element_stash, element_read) = {
 			let path = element_stash_path(forest_path.as_ref());
 			let stash = ItemStash::open(path)?;
 			let read = stash.to_element_read()?;
 			(stash, read)
 		};
-		let key_store = build_keys_store(key_store_path(forest_path).as_path())?;
-		let forest = Self { element_stash, element_read: Rc::new(element_read), key_store };
+		let key_store = build_key_store(key_store_path(&forest_path).as_path(), options.max_key_bytes)?;
+		let value_store = V::open_store(value_store_path(&forest_path).as_path())?;
+		let forest = Self {
+			element_stash,
+			element_read: Rc::new(element_read),
+			key_store,
+			value_store,
+			commit_log,
+			forest_path: forest_path.as_ref().to_path_buf(),
+			recovery,
+			build_key_store,
+			options,
+			refs: RefTable::open(refs_path(&forest_path))?,
+		};
 		Ok(forest)
 	}
-	pub fn add_root(&mut self) -> io::Result<RootIndex> {
+	pub fn add_root(&mut self) -> Result<RootIndex> {
 		let index = RootIndex(ElementStoreIndex(0));
 		Ok(index)
 	}
-	pub fn find(&self, root_index: RootIndex, search_key: &K) -> Option<u32> {
-		let trie = self.trie(root_index).expect("find trie at index");
-		trie.find(search_key, &self.key_store).cloned()
+	pub fn latest_root(&self) -> RootIndex {
+		self.commit_log.latest_commit().map(|record| record.root_index).unwrap_or(RootIndex(ElementStoreIndex(0)))
+	}
+	pub fn recovery(&self) -> &Recovery { &self.recovery }
+	pub fn commit(&mut self, root_index: RootIndex) -> Result<()> {
+		let parent = self.commit_log.latest_commit().map(|record| record.root_index);
+		self.commit_with_parent(root_index, parent, now_millis(), None)
+	}
+	fn commit_with_parent(&mut self, root_index: RootIndex, parent: Option<RootIndex>, timestamp: u64, ref_name: Option<&str>) -> Result<()> {
+		let _root = self.trie(root_index)?;
+		self.value_store.sync()?;
+		self.key_store.sync()?;
+		self.element_stash.sync()?;
+		let lengths = StoreLengths::read(&self.forest_path)?;
+		self.commit_log.append(CommitRecord { root_index, parent, timestamp, lengths, ref_name: ref_name.map(str::to_string) })
+	}
+	pub fn find(&self, root_index: RootIndex, search_key: &K) -> Result<Option<V>> {
+		let trie = self.trie(root_index)?;
+		let value = match trie.find(search_key, &self.key_store)? {
+			None => None,
+			Some(value) => Some(self.value_store.read_value(ValueField::from(*value))?),
+		};
+		Ok(value)
+	}
+	pub fn push(&mut self, root_index: RootIndex, insert_key: K, value: V) -> Result<RootIndex> {
+		let trie = self.trie(root_index)?;
+		let value = self.value_store.write_value(&value)?;
+		let new_trie = trie.push(insert_key, value.to_u32(), &mut self.key_store)?;
+		let new_root_index = self.save(new_trie)?;
+		Ok(RootIndex(new_root_index))
 	}
-	pub fn push(&mut self, root_index: RootIndex, insert_key: K, value: u32) -> io::Result<RootIndex> {
+	pub fn remove(&mut self, root_index: RootIndex, remove_key: &K) -> Result<RootIndex> {
 		let trie = self.trie(root_index)?;
-		let new_trie = trie.push(insert_key, value, &mut self.key_store);
+		let new_trie = trie.remove(remove_key, &self.key_store)?;
+		if !new_trie.is_data_direct() {
+			return Ok(root_index);
+		}
 		let new_root_index = self.save(new_trie)?;
 		Ok(RootIndex(new_root_index))
 	}
-	fn save(&mut self, root_trie: Trie) -> io::Result<ElementStoreIndex> {
+	pub fn batch(&mut self, root_index: RootIndex) -> Result<KvBatch<'_, K, V>> {
+		KvBatch::new(self, root_index)
+	}
+	pub fn iter(&self, root_index: RootIndex) -> Result<impl Iterator<Item=Result<(K, V)>> + '_> {
+		let trie = self.trie(root_index)?;
+		let iter = trie.entries().map(|entry| self.read_entry(entry?));
+		Ok(iter)
+	}
+	fn read_entry(&self, (key, value): (KeyField, u32)) -> Result<(K, V)> {
+		let key = self.key_store.read_key(KeyStoreIndex::from(&key))?;
+		let value = self.value_store.read_value(ValueField::from(value))?;
+		Ok((key, value))
+	}
+	fn save(&mut self, root_trie: Trie) -> Result<ElementStoreIndex> {
 		let mut relocation_tasks = Vec::new();
 		{
 			let mut relocation_search = vec![(0, &root_trie)];
@@ -161,17 +330,35 @@ impl<K: Key> KvForest<K> {
 		let saved_stash_index = self.element_stash.append([new_trie.to_u32s()])?;
 		Ok(saved_stash_index)
 	}
-	fn trie(&self, root_index: RootIndex) -> io::Result<Trie> {
-		let root_bytes = self.element_read.read(root_index.0)?;
-		let trie = Trie::parse(&root_bytes, self.element_read.clone()).expect("trie root");
-		Ok(trie)
+	fn trie(&self, root_index: RootIndex) -> Result<Trie> {
+		let root_bytes = match self.element_read.read_node(root_index.0, 1) {
+			Err(Error::Io(error)) if error.kind() == ErrorKind::UnexpectedEof => return Err(Error::BadRootIndex(root_index)),
+			read => read?[0],
+		};
+		Trie::parse(&root_bytes, self.element_read.clone()).ok_or(Error::BadRootIndex(root_index))
 	}
 }
 
-fn element_stash_path(forest_path: impl AsRef<Path>) -> PathBuf {
+pub(crate) fn now_millis() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_millis() as u64).unwrap_or(0)
+}
+
+pub(crate) fn element_stash_path(forest_path: impl AsRef<Path>) -> PathBuf {
 	forest_path.as_ref().join("elements.stash")
 }
 
-fn key_store_path(forest_path: impl AsRef<Path>) -> PathBuf {
+pub(crate) fn key_store_path(forest_path: impl AsRef<Path>) -> PathBuf {
 	forest_path.as_ref().join("keys.stash")
 }
+
+pub(crate) fn value_store_path(forest_path: impl AsRef<Path>) -> PathBuf {
+	forest_path.as_ref().join("values.stash")
+}
+
+pub(crate) fn commit_log_path(forest_path: impl AsRef<Path>) -> PathBuf {
+	forest_path.as_ref().join("commits.log")
+}
+
+pub(crate) fn refs_path(forest_path: impl AsRef<Path>) -> PathBuf {
+	forest_path.as_ref().join("refs.json")
+}
@@ -0,0 +1,108 @@
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::header::{FileHeader, FileKind, HEADER_BYTES};
+use crate::item_stash::element::ElementStoreIndex;
+use crate::item_stash::element_store::node_records_end;
+use crate::item_stash::stash::store_path;
+use crate::key_store::bytes::key_records_end;
+use crate::key_store::ordered::record_bytes;
+use crate::key_store::u32::RECORD_BYTES as U32_RECORD_BYTES;
+use crate::kv_forest::{element_stash_path, key_store_path, RootIndex, value_store_path};
+use crate::kv_forest::commit_log::CommitLog;
+use crate::value_store::bytes::value_records_end;
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct StoreLengths {
+	pub elements: u64,
+	pub keys: u64,
+	pub values: u64,
+}
+
+impl StoreLengths {
+	fn complete(forest_path: &Path) -> Result<Self> {
+		let lengths = Self {
+			elements: complete_length(&store_path(element_stash_path(forest_path)))?,
+			keys: complete_length(&key_store_path(forest_path))?,
+			values: complete_length(&value_store_path(forest_path))?,
+		};
+		Ok(lengths)
+	}
+	pub(crate) fn read(forest_path: &Path) -> Result<Self> {
+		let lengths = Self {
+			elements: file_length(&store_path(element_stash_path(forest_path)))?,
+			keys: file_length(&key_store_path(forest_path))?,
+			values: file_length(&value_store_path(forest_path))?,
+		};
+		Ok(lengths)
+	}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Recovery {
+	pub root_index: RootIndex,
+	pub discarded: StoreLengths,
+}
+
+impl Recovery {
+	pub fn is_clean(&self) -> bool { self.discarded == StoreLengths::default() }
+}
+
+pub(crate) fn recover(forest_path: &Path, commit_log: &CommitLog, roll_back: bool) -> Result<Recovery> {
+	let root_index = commit_log.latest_commit().map(|record| record.root_index).unwrap_or(RootIndex(ElementStoreIndex(0)));
+	let lengths = match commit_log.latest().filter(|_| roll_back) {
+		Some(record) => record.lengths,
+		None => StoreLengths::complete(forest_path)?,
+	};
+	let discarded = StoreLengths {
+		elements: truncate_to(&store_path(element_stash_path(forest_path)), lengths.elements)?,
+		keys: truncate_to(&key_store_path(forest_path), lengths.keys)?,
+		values: truncate_to(&value_store_path(forest_path), lengths.values)?,
+	};
+	Ok(Recovery { root_index, discarded })
+}
+
+fn file_length(path: &Path) -> Result<u64> {
+	match path.metadata() {
+		Err(error) if error.kind() == ErrorKind::NotFound => Ok(0),
+		metadata => Ok(metadata?.len()),
+	}
+}
+
+fn complete_length(path: &Path) -> Result<u64> {
+	let file = match File::open(path) {
+		Err(error) if error.kind() == ErrorKind::NotFound => return Ok(0),
+		open => open?,
+	};
+	let header = FileHeader::read(&file)?;
+	let length = file.metadata()?.len();
+	let whole_records = |record_bytes: u64| HEADER_BYTES + (length - HEADER_BYTES) / record_bytes * record_bytes;
+	match header.kind {
+		FileKind::Elements => node_records_end(&file),
+		FileKind::U32Keys => Ok(whole_records(U32_RECORD_BYTES)),
+		FileKind::U64Keys => Ok(whole_records(record_bytes::<u64>())),
+		FileKind::I64Keys => Ok(whole_records(record_bytes::<i64>())),
+		FileKind::I32Keys => Ok(whole_records(record_bytes::<i32>())),
+		FileKind::StringKeys | FileKind::BytesKeys => key_records_end(&file, header),
+		FileKind::Values => value_records_end(&file),
+		FileKind::Commits | FileKind::Refs => Ok(length),
+	}
+}
+
+fn truncate_to(path: &Path, committed: u64) -> Result<u64> {
+	let file = match OpenOptions::new().write(true).open(path) {
+		Err(error) if error.kind() == ErrorKind::NotFound => return Ok(0),
+		open => open?,
+	};
+	let length = file.metadata()?.len();
+	if length < committed {
+		return Err(Error::TruncatedStore { path: path.to_path_buf(), length, committed });
+	}
+	if length > committed {
+		file.set_len(committed)?;
+		file.sync_data()?;
+	}
+	Ok(length - committed)
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::header::{check_file, FileHeader, FileKind, HEADER_BYTES};
+use crate::key_store::Key;
+use crate::kv_forest::{KvForest, now_millis, RootIndex};
+use crate::value_store::Value;
+
+#[derive(Serialize, Deserialize)]
+struct RefsFile {
+	refs: BTreeMap<String, u32>,
+}
+
+pub(crate) struct RefTable {
+	path: PathBuf,
+	refs: BTreeMap<String, RootIndex>,
+}
+
+impl RefTable {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+		let refs = match File::open(&path) {
+			Err(error) if error.kind() == ErrorKind::NotFound => BTreeMap::new(),
+			file => read_refs(file?)?,
+		};
+		Ok(Self { path, refs })
+	}
+	pub fn get(&self, name: &str) -> Option<RootIndex> { self.refs.get(name).copied() }
+	pub fn list(&self) -> Vec<(String, RootIndex)> {
+		self.refs.iter().map(|(name, root)| (name.clone(), *root)).collect()
+	}
+	pub fn roots(&self) -> impl Iterator<Item=RootIndex> + '_ { self.refs.values().copied() }
+	pub fn update(&mut self, name: &str, root: Option<RootIndex>) -> Result<()> {
+		let mut refs = self.refs.clone();
+		match root {
+			None => refs.remove(name),
+			Some(root) => refs.insert(name.to_string(), root),
+		};
+		write_refs(&self.path, &refs)?;
+		self.refs = refs;
+		Ok(())
+	}
+}
+
+fn read_refs(mut file: File) -> Result<BTreeMap<String, RootIndex>> {
+	check_file(&file, FileKind::Refs)?;
+	let mut bytes = Vec::new();
+	file.seek(SeekFrom::Start(HEADER_BYTES))?;
+	file.read_to_end(&mut bytes)?;
+	let refs_file = serde_json::from_slice::<RefsFile>(&bytes).map_err(Error::CorruptRefs)?;
+	Ok(refs_file.refs.into_iter().map(|(name, root)| (name, RootIndex::from(root))).collect())
+}
+
+fn write_refs(path: &Path, refs: &BTreeMap<String, RootIndex>) -> Result<()> {
+	let file = RefsFile {
+		refs: refs.iter().map(|(name, root)| (name.clone(), root.0.0)).collect(),
+	};
+	let bytes = serde_json::to_vec_pretty(&file).map_err(Error::CorruptRefs)?;
+	let tmp_path = path.with_extension("tmp");
+	{
+		let mut tmp = File::create(&tmp_path)?;
+		tmp.write_all(&FileHeader::current(FileKind::Refs).to_bytes())?;
+		tmp.write_all(&bytes)?;
+		tmp.sync_all()?;
+	}
+	fs::rename(&tmp_path, path)?;
+	if let Some(dir) = path.parent() {
+		File::open(dir)?.sync_all()?;
+	}
+	Ok(())
+}
+
+impl<K: Key, V: Value> KvForest<K, V> {
+	pub fn get_ref(&self, name: &str) -> Option<RootIndex> {
+		self.refs.get(name)
+	}
+	pub fn list_refs(&self) -> Vec<(String, RootIndex)> {
+		self.refs.list()
+	}
+	pub fn set_ref(&mut self, name: &str, root_index: RootIndex) -> Result<()> {
+		self.commit_ref(name, root_index)?;
+		self.refs.update(name, Some(root_index))
+	}
+	pub fn delete_ref(&mut self, name: &str) -> Result<Option<RootIndex>> {
+		let previous = self.refs.get(name);
+		if previous.is_some() {
+			self.refs.update(name, None)?;
+		}
+		Ok(previous)
+	}
+	fn commit_ref(&mut self, name: &str, root_index: RootIndex) -> Result<()> {
+		let parent = self.refs.get(name);
+		self.commit_with_parent(root_index, parent, now_millis(), Some(name))
+	}
+	pub fn compare_and_set_ref(&mut self, name: &str, expected: Option<RootIndex>, new: Option<RootIndex>) -> Result<()> {
+		let found = self.refs.get(name);
+		if found != expected {
+			return Err(Error::RefConflict { name: name.to_string(), expected, found });
+		}
+		if let Some(root_index) = new {
+			self.commit_ref(name, root_index)?;
+		}
+		self.refs.update(name, new)
+	}
+}
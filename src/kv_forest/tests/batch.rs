@@ -0,0 +1,56 @@
+use std::fs;
+
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn batch_of_thousand_finds_all_values_after_reopen() {
+	let path = prepare_kv_store_test_dir("batch-thousand");
+	let index = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let index = forest.add_root().expect("add-root");
+		let mut batch = forest.batch(index).expect("batch");
+		for i in 0..1000 {
+			batch.push(i * 71, i + 1).expect("push");
+		}
+		batch.remove(&0).expect("remove");
+		batch.commit().expect("commit")
+	};
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let trie = forest.trie(index).expect("trie at index");
+	assert_eq!(999, trie.size());
+	assert_eq!(None, forest.find(index, &0).expect("find"));
+	for i in 1..1000 {
+		assert_eq!(Some(i + 1), forest.find(index, &(i * 71)).expect("find"));
+	}
+}
+
+#[test]
+fn batch_writes_less_than_single_pushes() {
+	let path = prepare_kv_store_test_dir("batch-growth");
+	let mut single = KvForest::<u32>::open(path.join("single")).expect("open or create");
+	let mut index = single.add_root().expect("add-root");
+	for i in 0..200 {
+		index = single.push(index, i * 71, i).expect("push");
+	}
+	let mut batched = KvForest::<u32>::open(path.join("batched")).expect("open or create");
+	let index = batched.add_root().expect("add-root");
+	let mut batch = batched.batch(index).expect("batch");
+	for i in 0..200 {
+		batch.push(i * 71, i).expect("push");
+	}
+	let _ = batch.commit().expect("commit");
+	let stash_len = |forest: &str| fs::metadata(path.join(forest).join("elements.stash").join("elements.store")).expect("metadata").len();
+	assert!(stash_len("batched") * 10 < stash_len("single"));
+}
+
+#[test]
+fn batch_without_changes_keeps_root() {
+	let path = prepare_kv_store_test_dir("batch-unchanged");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("add-root");
+	let index = forest.push(index, 5, 5).expect("push");
+	let mut batch = forest.batch(index).expect("batch");
+	batch.remove(&6).expect("remove");
+	assert_eq!(index, batch.commit().expect("commit"));
+}
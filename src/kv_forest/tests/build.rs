@@ -0,0 +1,54 @@
+use crate::{Error, Result};
+
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn build_from_sorted_matches_pushed_trie() {
+	let path = prepare_kv_store_test_dir("build-matches-push");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let built = forest.build_from_sorted((0..1000).map(|i| (i * 71, i + 1))).expect("build");
+	let mut pushed = forest.add_root().expect("add-root");
+	for i in 0..1000 {
+		pushed = forest.push(pushed, i * 71, i + 1).expect("push");
+	}
+	let built_trie = forest.trie(built).expect("built trie");
+	let pushed_trie = forest.trie(pushed).expect("pushed trie");
+	assert_eq!(1000, built_trie.size());
+	assert_eq!(pushed_trie.map, built_trie.map);
+	for i in 0..1000 {
+		assert_eq!(Some(i + 1), forest.find(built, &(i * 71)).expect("find"));
+	}
+	let built_pairs = forest.iter(built).expect("iter").collect::<Result<Vec<_>>>().expect("pairs");
+	let pushed_pairs = forest.iter(pushed).expect("iter").collect::<Result<Vec<_>>>().expect("pairs");
+	assert_eq!(pushed_pairs, built_pairs);
+}
+
+#[test]
+fn build_from_sorted_strings() {
+	let path = prepare_kv_store_test_dir("build-strings");
+	let mut forest = KvForest::<String>::open(path.join("forest")).expect("open or create");
+	let keys = ["", "a", "ab", "abc", "b", "ba"];
+	let root = forest.build_from_sorted(keys.iter().enumerate().map(|(value, key)| (key.to_string(), value as u32))).expect("build");
+	for (value, key) in keys.iter().enumerate() {
+		assert_eq!(Some(value as u32), forest.find(root, &key.to_string()).expect("find"));
+	}
+}
+
+#[test]
+fn build_from_empty_iterator() {
+	let path = prepare_kv_store_test_dir("build-empty");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let root = forest.build_from_sorted([]).expect("build");
+	assert_eq!(0, forest.trie(root).expect("trie").size());
+}
+
+#[test]
+fn build_rejects_unsorted_and_duplicate_keys() {
+	let path = prepare_kv_store_test_dir("build-unsorted");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let error = forest.build_from_sorted([(2, 0), (1, 0)]).expect_err("unsorted error");
+	assert!(matches!(error, Error::UnsortedKeys));
+	let error = forest.build_from_sorted([(1, 0), (1, 0)]).expect_err("duplicate error");
+	assert!(matches!(error, Error::UnsortedKeys));
+}
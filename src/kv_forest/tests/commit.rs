@@ -0,0 +1,171 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::Error;
+use crate::checksum::crc32;
+use crate::header::{FileHeader, FileKind, HEADER_BYTES};
+use crate::kv_forest::{ForestOptions, KvForest, RootIndex};
+use crate::kv_forest::recovery::StoreLengths;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+const KEEP_UNCOMMITTED: ForestOptions = ForestOptions { keep_uncommitted: true, max_key_bytes: None };
+
+#[test]
+fn latest_root_survives_reopen() {
+	let path = prepare_kv_store_test_dir("commit-reopen");
+	let committed = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		assert_eq!(RootIndex::from(0), forest.latest_root());
+		let index = forest.push(forest.latest_root(), 1, 10).expect("push");
+		forest.commit(index).expect("commit");
+		let uncommitted = forest.push(index, 2, 20).expect("push");
+		assert_ne!(uncommitted, forest.latest_root());
+		index
+	};
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(committed, forest.latest_root());
+	assert_eq!(Some(10), forest.find(forest.latest_root(), &1).expect("find"));
+	assert_eq!(None, forest.find(forest.latest_root(), &2).expect("find"));
+}
+
+#[test]
+fn torn_commit_record_is_ignored() {
+	let path = prepare_kv_store_test_dir("commit-torn");
+	let committed = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let mut batch = forest.batch(forest.latest_root()).expect("batch");
+		batch.push(7, 70).expect("push");
+		batch.commit().expect("commit")
+	};
+	let log_path = path.join("forest").join("commits.log");
+	let mut log = OpenOptions::new().append(true).open(&log_path).expect("open log");
+	log.write_all(&[0xde; 34]).expect("write torn records");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(committed, forest.latest_root());
+	let index = forest.push(committed, 8, 80).expect("push");
+	forest.commit(index).expect("commit");
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen again");
+	assert_eq!(index, forest.latest_root());
+	assert_eq!(Some(70), forest.find(index, &7).expect("find"));
+}
+
+#[test]
+fn version_three_logs_are_rewritten() {
+	let path = prepare_kv_store_test_dir("commit-v3");
+	let log_path = path.join("forest").join("commits.log");
+	let committed = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let index = forest.push(forest.latest_root(), 5, 50).expect("push");
+		forest.commit(index).expect("commit");
+		index
+	};
+	let current = fs::read(&log_path).expect("read log");
+	let fields = &current[HEADER_BYTES as usize..HEADER_BYTES as usize + 40];
+	let mut old = FileHeader { kind: FileKind::Commits, version: 3 }.to_bytes().to_vec();
+	old.extend_from_slice(fields);
+	old.extend_from_slice(&crc32(fields).to_be_bytes());
+	fs::write(&log_path, old).expect("write v3 log");
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(committed, forest.latest_root());
+	assert_eq!(current, fs::read(&log_path).expect("read rewritten log"));
+}
+
+#[test]
+fn version_two_logs_are_rewritten() {
+	let path = prepare_kv_store_test_dir("commit-v2");
+	let log_path = path.join("forest").join("commits.log");
+	let committed = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let index = forest.push(forest.latest_root(), 5, 50).expect("push");
+		forest.commit(index).expect("commit");
+		index
+	};
+	let current = fs::read(&log_path).expect("read log");
+	let fields = &current[HEADER_BYTES as usize..HEADER_BYTES as usize + 40];
+	let mut checked = fields[0..4].to_vec();
+	checked.extend_from_slice(&fields[16..40]);
+	let mut old = FileHeader { kind: FileKind::Commits, version: 2 }.to_bytes().to_vec();
+	old.extend_from_slice(&checked);
+	old.extend_from_slice(&crc32(&checked).to_be_bytes());
+	fs::write(&log_path, old).expect("write v2 log");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(committed, forest.latest_root());
+	let rewritten = fs::read(&log_path).expect("read rewritten log");
+	assert_eq!(FileHeader::current(FileKind::Commits).to_bytes(), rewritten[0..HEADER_BYTES as usize]);
+	let index = forest.push(committed, 6, 60).expect("push");
+	forest.commit(index).expect("commit");
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen again");
+	assert_eq!(Some(50), forest.find(forest.latest_root(), &5).expect("find"));
+	assert_eq!(Some(committed), forest.commit_log.latest().and_then(|record| record.parent));
+}
+
+#[test]
+fn open_discards_writes_after_last_commit() {
+	let path = prepare_kv_store_test_dir("commit-discard");
+	let committed = {
+		let mut forest = KvForest::<String, String>::open(path.join("forest")).expect("open or create");
+		assert!(forest.recovery().is_clean());
+		let index = forest.push(forest.latest_root(), "kept".to_string(), "yes".to_string()).expect("push");
+		forest.commit(index).expect("commit");
+		let _lost = forest.push(index, "lost-key".to_string(), "no".to_string()).expect("push");
+		index
+	};
+	let forest_path = path.join("forest");
+	let mut elements = OpenOptions::new().append(true).open(forest_path.join("elements.stash").join("elements.store")).expect("open elements");
+	elements.write_all(&[1, 2, 3]).expect("write partial element");
+	let mut keys = OpenOptions::new().append(true).open(forest_path.join("keys.stash")).expect("open keys");
+	keys.write_all(&[0, 9, b'h']).expect("write partial key");
+
+	let forest = KvForest::<String, String>::open(&forest_path).expect("reopen");
+	let recovery = forest.recovery();
+	assert_eq!(committed, recovery.root_index);
+	assert_eq!(3, recovery.discarded.elements % 8);
+	assert_eq!(StoreLengths { elements: recovery.discarded.elements, keys: 1 + 8 + 3, values: 4 + 2 }, recovery.discarded);
+	assert_eq!(Some("yes".to_string()), forest.find(committed, &"kept".to_string()).expect("find"));
+	let forest = KvForest::<String, String>::open(&forest_path).expect("reopen again");
+	assert!(forest.recovery().is_clean());
+}
+
+#[test]
+fn open_keeps_uncommitted_writes_when_asked() {
+	let path = prepare_kv_store_test_dir("commit-keep-uncommitted");
+	let forest_path = path.join("forest");
+	let (committed, uncommitted) = {
+		let mut forest = KvForest::<String, String>::open(&forest_path).expect("open or create");
+		let committed = forest.push(forest.latest_root(), "kept".to_string(), "yes".to_string()).expect("push");
+		forest.commit(committed).expect("commit");
+		let uncommitted = forest.push(committed, "extra".to_string(), "also".to_string()).expect("push");
+		(committed, uncommitted)
+	};
+	let mut elements = OpenOptions::new().append(true).open(forest_path.join("elements.stash").join("elements.store")).expect("open elements");
+	elements.write_all(&[0xde; 19]).expect("write partial node");
+	let mut keys = OpenOptions::new().append(true).open(forest_path.join("keys.stash")).expect("open keys");
+	keys.write_all(&[9, b'h']).expect("write partial key");
+	let mut values = OpenOptions::new().append(true).open(forest_path.join("values.stash")).expect("open values");
+	values.write_all(&[0, 0]).expect("write partial value size");
+
+	let forest = KvForest::<String, String>::open_with_options(&forest_path, KEEP_UNCOMMITTED).expect("reopen");
+	assert_eq!(StoreLengths { elements: 19, keys: 2, values: 2 }, forest.recovery().discarded);
+	assert_eq!(committed, forest.latest_root());
+	assert_eq!(Some("also".to_string()), forest.find(uncommitted, &"extra".to_string()).expect("find"));
+	drop(forest);
+	let forest = KvForest::<String, String>::open(&forest_path).expect("reopen rolling back");
+	assert!(!forest.recovery().is_clean());
+	assert_eq!(Some("yes".to_string()), forest.find(committed, &"kept".to_string()).expect("find"));
+	let forest = KvForest::<String, String>::open(&forest_path).expect("reopen again");
+	assert!(forest.recovery().is_clean());
+}
+
+#[test]
+fn commit_rejects_bad_roots() {
+	let path = prepare_kv_store_test_dir("commit-bad-root");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let index = forest.push(forest.latest_root(), 1, 10).expect("push");
+	forest.commit(index).expect("commit");
+	let error = forest.commit(RootIndex::from(1000)).expect_err("out of bounds");
+	assert!(matches!(error, Error::BadRootIndex(_)));
+	let error = forest.commit(RootIndex::from(1)).expect_err("not a root");
+	assert!(matches!(error, Error::BadRootIndex(_) | Error::Checksum(_)));
+	assert_eq!(index, forest.latest_root());
+}
@@ -0,0 +1,53 @@
+use std::fs;
+
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+use crate::verify::check_forest;
+
+#[test]
+fn compact_keeps_live_roots_and_shrinks_files() {
+	let path = prepare_kv_store_test_dir("compact-shrinks");
+	let forest_path = path.join("forest");
+	let mut forest = KvForest::<String, String>::open(&forest_path).expect("open or create");
+	for round in 0..10 {
+		let mut round_root = forest.add_root().expect("add-root");
+		for i in 0..50 {
+			round_root = forest.push(round_root, format!("key-{}-{}", round, i), format!("value-{}-{}", i, round)).expect("push");
+		}
+		forest.commit(round_root).expect("commit");
+	}
+	let index = forest.latest_root();
+	let old_root = forest.add_root().expect("add-root");
+	let old_root = forest.push(old_root, "old".to_string(), "kept".to_string()).expect("push");
+	let elements_path = forest_path.join("elements.stash").join("elements.store");
+	let elements_before = fs::metadata(&elements_path).expect("metadata").len();
+	let keys_before = fs::metadata(forest_path.join("keys.stash")).expect("metadata").len();
+
+	let remapped = forest.compact(&[old_root]).expect("compact");
+	assert_eq!(2, remapped.len());
+	assert!(fs::metadata(&elements_path).expect("metadata").len() * 5 < elements_before);
+	assert!(fs::metadata(forest_path.join("keys.stash")).expect("metadata").len() * 5 < keys_before);
+	let latest = forest.latest_root();
+	assert_eq!(remapped[&index], latest);
+	for i in 0..50 {
+		assert_eq!(Some(format!("value-{}-9", i)), forest.find(latest, &format!("key-9-{}", i)).expect("find"));
+	}
+	assert_eq!(Some("kept".to_string()), forest.find(remapped[&old_root], &"old".to_string()).expect("find"));
+	drop(forest);
+
+	let forest = KvForest::<String, String>::open(&forest_path).expect("reopen");
+	assert!(forest.recovery().is_clean());
+	assert_eq!(latest, forest.latest_root());
+	assert_eq!(Some("value-3-9".to_string()), forest.find(latest, &"key-9-3".to_string()).expect("find"));
+	assert!(check_forest(&forest_path).expect("check").is_ok());
+}
+
+#[test]
+fn open_discards_unfinished_compaction() {
+	let path = prepare_kv_store_test_dir("compact-unfinished");
+	let forest_path = path.join("forest");
+	KvForest::<u32>::open(&forest_path).expect("open or create");
+	fs::create_dir(forest_path.join("compact.pending")).expect("create pending");
+	KvForest::<u32>::open(&forest_path).expect("reopen");
+	assert!(!forest_path.join("compact.pending").exists());
+}
@@ -0,0 +1,48 @@
+use std::fs::OpenOptions;
+use std::os::unix::fs::FileExt;
+
+use crate::header::HEADER_BYTES;
+use crate::kv_forest::diff::Diff;
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn diff_reports_added_removed_and_changed() {
+	let path = prepare_kv_store_test_dir("diff-u32");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let old = forest.build_from_sorted((0..1000).map(|i| (i * 71, i))).expect("build");
+	let new = forest.push(old, 5, 5).expect("push");
+	let new = forest.push(new, 71, 7100).expect("push");
+	let new = forest.remove(new, &(71 * 999)).expect("remove");
+	let new = forest.push(new, 142, 2).expect("push");
+	let mut diffs = forest.diff(old, new).expect("diff");
+	diffs.sort_by_key(|diff| match diff {
+		Diff::Added(key, _) | Diff::Removed(key, _) | Diff::Changed(key, _, _) => *key,
+	});
+	assert_eq!(vec![Diff::Added(5, 5), Diff::Changed(71, 1, 7100), Diff::Removed(71 * 999, 999)], diffs);
+	assert!(forest.diff(new, new).expect("diff").is_empty());
+	assert_eq!(3, forest.diff(new, old).expect("reverse diff").len());
+}
+
+#[test]
+fn diff_compares_leaf_against_sub_trie() {
+	let path = prepare_kv_store_test_dir("diff-strings");
+	let mut forest = KvForest::<String, String>::open(path.join("forest")).expect("open or create");
+	let old = forest.push(forest.latest_root(), "a".to_string(), "1".to_string()).expect("push");
+	let new = forest.push(old, "ab".to_string(), "2".to_string()).expect("push");
+	let new = forest.push(new, "a".to_string(), "1".to_string()).expect("push");
+	assert_eq!(vec![Diff::Added("ab".to_string(), "2".to_string())], forest.diff(old, new).expect("diff"));
+	assert_eq!(vec![Diff::Removed("ab".to_string(), "2".to_string())], forest.diff(new, old).expect("diff"));
+}
+
+#[test]
+fn diff_skips_identical_fields_without_reading_them() {
+	let path = prepare_kv_store_test_dir("diff-identical");
+	let forest_path = path.join("forest");
+	let mut forest = KvForest::<String>::open(&forest_path).expect("open or create");
+	let old = forest.push(forest.latest_root(), "alpha-key".to_string(), 1).expect("push");
+	let new = forest.push(old, "alpha-key-2".to_string(), 2).expect("push");
+	let keys = OpenOptions::new().write(true).open(forest_path.join("keys.stash")).expect("open keys");
+	keys.write_all_at(&[0xff], HEADER_BYTES + 2).expect("corrupt unchanged key");
+	assert_eq!(vec![Diff::Added("alpha-key-2".to_string(), 2)], forest.diff(old, new).expect("diff"));
+}
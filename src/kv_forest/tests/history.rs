@@ -0,0 +1,76 @@
+use crate::Error;
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn history_walks_ref_versions_after_reopen() {
+	let path = prepare_kv_store_test_dir("history-walk");
+	let roots = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let mut roots = Vec::new();
+		let mut index = forest.latest_root();
+		for i in 0..3 {
+			index = forest.push(index, i, i).expect("push");
+			forest.set_ref("main", index).expect("set main");
+			roots.push(index);
+		}
+		let side = forest.push(roots[0], 100, 100).expect("push");
+		forest.set_ref("side", side).expect("set side");
+		forest.set_ref("copy", roots[2]).expect("set copy");
+		roots
+	};
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	let history = forest.history("main").expect("history");
+	assert_eq!(vec![roots[2], roots[1], roots[0]], history.iter().map(|entry| entry.root_index).collect::<Vec<_>>());
+	assert_eq!(Some(roots[1]), history[0].parent);
+	assert_eq!(None, history[2].parent);
+	assert!(history[0].committed_at >= history[2].committed_at);
+	assert_eq!(1, forest.history("side").expect("side history").len());
+	let copy = forest.history("copy").expect("copy history");
+	assert_eq!(vec![roots[2]], copy.iter().map(|entry| entry.root_index).collect::<Vec<_>>());
+	assert_eq!(None, copy[0].parent);
+	assert!(matches!(forest.history("missing"), Err(Error::UnknownRef(_))));
+}
+
+#[test]
+fn rollback_resets_ref_to_earlier_version() {
+	let path = prepare_kv_store_test_dir("history-rollback");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let before = forest.push(forest.latest_root(), 1, 1).expect("push");
+	forest.set_ref("main", before).expect("set main");
+	let import = forest.push(before, 2, 2).expect("push");
+	forest.set_ref("main", import).expect("set main");
+	let unrelated = forest.push(forest.latest_root(), 3, 3).expect("push");
+	forest.commit(unrelated).expect("commit");
+
+	let error = forest.rollback("main", unrelated).expect_err("not in history");
+	assert!(matches!(error, Error::NotInHistory { .. }));
+	forest.rollback("main", before).expect("rollback");
+	assert_eq!(Some(before), forest.get_ref("main"));
+	assert_eq!(None, forest.find(forest.get_ref("main").expect("main"), &2).expect("find"));
+	let history = forest.history("main").expect("history");
+	assert_eq!(vec![before, import, before], history.iter().map(|entry| entry.root_index).collect::<Vec<_>>());
+	assert_eq!(Some(import), history[0].parent);
+	assert_eq!(unrelated, forest.latest_root());
+}
+
+#[test]
+fn compact_drops_history_of_pruned_roots() {
+	let path = prepare_kv_store_test_dir("history-compact");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.latest_root();
+	let mut roots = Vec::new();
+	for i in 0..3 {
+		index = forest.push(index, i, i).expect("push");
+		forest.set_ref("main", index).expect("set main");
+		roots.push(index);
+	}
+	assert_eq!(3, forest.history("main").expect("history").len());
+	let remapped = forest.compact(&[roots[1]]).expect("compact");
+	assert!(!remapped.contains_key(&roots[0]));
+	let history = forest.history("main").expect("history after compact");
+	assert_eq!(vec![remapped[&roots[2]], remapped[&roots[1]]], history.iter().map(|entry| entry.root_index).collect::<Vec<_>>());
+	assert_eq!(None, history[1].parent);
+	let error = forest.rollback("main", roots[0]).expect_err("pruned root");
+	assert!(matches!(error, Error::NotInHistory { .. }));
+}
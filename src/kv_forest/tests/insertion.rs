@@ -11,11 +11,11 @@ fn insert_two_keys_with_same_prefix_and_depth_and_two_more_at_lower_depth_finds_
 	let index = forest.push(index, 0b000000001000010, 4).expect("push");
 	let trie = forest.trie(index).expect("trie at index");
 	assert_eq!(4, trie.size());
-	assert_eq!(Some(1), forest.find(index, &0b000000000100000));
-	assert_eq!(Some(2), forest.find(index, &0b000000001000000));
-	assert_eq!(Some(3), forest.find(index, &0b000000001000001));
-	assert_eq!(Some(4), forest.find(index, &0b000000001000010));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(1), forest.find(index, &0b000000000100000).expect("find"));
+	assert_eq!(Some(2), forest.find(index, &0b000000001000000).expect("find"));
+	assert_eq!(Some(3), forest.find(index, &0b000000001000001).expect("find"));
+	assert_eq!(Some(4), forest.find(index, &0b000000001000010).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
 }
 
 #[test]
@@ -28,10 +28,10 @@ fn insert_two_keys_with_same_prefix_and_depth_and_third_at_lower_depth_finds_all
 	let index = forest.push(index, 0b000000001000001, 3).expect("push");
 	let trie = forest.trie(index).expect("trie");
 	assert_eq!(3, trie.size());
-	assert_eq!(Some(1), forest.find(index, &0b000000000100000));
-	assert_eq!(Some(2), forest.find(index, &0b000000001000000));
-	assert_eq!(Some(3), forest.find(index, &0b000000001000001));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(1), forest.find(index, &0b000000000100000).expect("find"));
+	assert_eq!(Some(2), forest.find(index, &0b000000001000000).expect("find"));
+	assert_eq!(Some(3), forest.find(index, &0b000000001000001).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
 }
 
 #[test]
@@ -44,10 +44,10 @@ fn insert_three_keys_with_same_prefix_and_depth_finds_all_three_values() {
 	let index = forest.push(index, 0b000000001100000, 3).expect("push");
 	let trie = forest.trie(index).expect("trie");
 	assert_eq!(3, trie.size());
-	assert_eq!(Some(1), forest.find(index, &0b000000000100000));
-	assert_eq!(Some(2), forest.find(index, &0b000000001000000));
-	assert_eq!(Some(3), forest.find(index, &0b000000001100000));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(1), forest.find(index, &0b000000000100000).expect("find"));
+	assert_eq!(Some(2), forest.find(index, &0b000000001000000).expect("find"));
+	assert_eq!(Some(3), forest.find(index, &0b000000001100000).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
 }
 
 #[test]
@@ -59,9 +59,9 @@ fn insert_two_keys_with_same_prefix_and_depth_finds_both_values() {
 	let index = forest.push(index, 0b000000001000000, 2).expect("push");
 	let trie = forest.trie(index).expect("trie");
 	assert_eq!(2, trie.size());
-	assert_eq!(Some(1), forest.find(index, &0b000000000100000));
-	assert_eq!(Some(2), forest.find(index, &0b000000001000000));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(1), forest.find(index, &0b000000000100000).expect("find"));
+	assert_eq!(Some(2), forest.find(index, &0b000000001000000).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
 }
 
 #[test]
@@ -73,9 +73,9 @@ fn insert_two_keys_on_different_paths_finds_both_values() {
 	let index = forest.push(index, 0b000010000100000, 33).expect("push");
 	let trie = forest.trie(index).expect("trie");
 	assert_eq!(2, trie.size());
-	assert_eq!(Some(1), forest.find(index, &0b000000000100000));
-	assert_eq!(Some(33), forest.find(index, &0b000010000100000));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(1), forest.find(index, &0b000000000100000).expect("find"));
+	assert_eq!(Some(33), forest.find(index, &0b000010000100000).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
 }
 
 #[test]
@@ -87,8 +87,8 @@ fn insert_same_key_finds_last_value() {
 	let index = forest.push(index, 0b000010000000000, 2).expect("push");
 	let trie = forest.trie(index).expect("trie");
 	assert_eq!(1, trie.size());
-	assert_eq!(Some(2), forest.find(index, &0b000010000000000));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(2), forest.find(index, &0b000010000000000).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
 }
 
 #[test]
@@ -99,6 +99,56 @@ fn insert_value_finds_value() {
 	let index = forest.push(index, 0b000010000000000, 1).expect("push");
 	let trie = forest.trie(index).expect("trie");
 	assert_eq!(1, trie.size());
-	assert_eq!(Some(1), forest.find(index, &0b000010000000000));
-	assert_eq!(None, forest.find(index, &0));
+	assert_eq!(Some(1), forest.find(index, &0b000010000000000).expect("find"));
+	assert_eq!(None, forest.find(index, &0).expect("find"));
+}
+
+#[test]
+fn insert_strings_that_prefix_one_another_finds_all_values() {
+	let path = prepare_kv_store_test_dir("insert-h");
+	let mut forest = KvForest::<String>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	let keys = ["ab", "abc", "", "a", "abcd", "b"];
+	for (value, key) in keys.iter().enumerate() {
+		index = forest.push(index, key.to_string(), value as u32).expect("push");
+	}
+	for (value, key) in keys.iter().enumerate() {
+		assert_eq!(Some(value as u32), forest.find(index, &key.to_string()).expect("find"));
+	}
+	assert_eq!(None, forest.find(index, &"abcde".to_string()).expect("find"));
+	let found = forest.iter(index).expect("iter").map(|entry| entry.expect("entry").0).collect::<Vec<_>>();
+	assert_eq!(vec!["", "a", "ab", "abc", "abcd", "b"], found);
+	let found = forest.scan_prefix(index, "ab").expect("scan").count();
+	assert_eq!(3, found);
+}
+
+#[test]
+fn insert_boundary_keys_finds_all_values() {
+	let path = prepare_kv_store_test_dir("insert-i");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	let keys = [0, 0x3fff_ffff, 0x4000_0000, 0x7fff_ffff, 0x8000_0000, 0xc000_0000, u32::MAX];
+	for key in keys {
+		index = forest.push(index, key, !key).expect("push");
+	}
+	for key in keys {
+		assert_eq!(Some(!key), forest.find(index, &key).expect("find"));
+	}
+	let found = forest.range(index, 0x7fff_ffff..).expect("range").map(|entry| entry.expect("entry").0).collect::<Vec<_>>();
+	assert_eq!(vec![0x7fff_ffff, 0x8000_0000, 0xc000_0000, u32::MAX], found);
+	assert_eq!(Some((u32::MAX, 0)), forest.max(index).expect("max"));
+}
+
+#[test]
+fn forest_options_limit_key_length() {
+	let path = prepare_kv_store_test_dir("insert-max-key-bytes");
+	let options = ForestOptions { max_key_bytes: Some(8), ..ForestOptions::default() };
+	let mut forest = KvForest::<String>::open_with_options(path.join("forest"), options).expect("open or create");
+	let index = forest.push(forest.latest_root(), "eight-ch".to_string(), 8).expect("push");
+	let error = forest.push(index, "nine-char".to_string(), 9).expect_err("too long");
+	assert!(matches!(error, Error::KeyTooLong { len: 9, max: 8 }));
+	forest.commit(index).expect("commit");
+	forest.compact(&[]).expect("compact");
+	let error = forest.push(forest.latest_root(), "nine-char".to_string(), 9).expect_err("too long after compact");
+	assert!(matches!(error, Error::KeyTooLong { len: 9, max: 8 }));
 }
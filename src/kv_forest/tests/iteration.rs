@@ -0,0 +1,36 @@
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn iter_empty_root_yields_nothing() {
+	let path = prepare_kv_store_test_dir("iter-empty");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	assert_eq!(0, forest.iter(index).expect("iter").count());
+}
+
+#[test]
+fn iter_yields_every_pair() {
+	let path = prepare_kv_store_test_dir("iter-pairs");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	for i in 0..500 {
+		index = forest.push(index, i * 71, i + 1).expect("push");
+	}
+	let mut pairs = forest.iter(index).expect("iter").collect::<crate::Result<Vec<_>>>().expect("pairs");
+	pairs.sort();
+	let expected = (0..500).map(|i| (i * 71, i + 1)).collect::<Vec<_>>();
+	assert_eq!(expected, pairs);
+}
+
+#[test]
+fn iter_resolves_string_keys() {
+	let path = prepare_kv_store_test_dir("iter-strings");
+	let mut forest = KvForest::<String>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	let index = forest.push(index, "Hey".to_string(), 1).expect("push");
+	let index = forest.push(index, "You".to_string(), 2).expect("push");
+	let mut pairs = forest.iter(index).expect("iter").collect::<crate::Result<Vec<_>>>().expect("pairs");
+	pairs.sort();
+	assert_eq!(vec![("Hey".to_string(), 1), ("You".to_string(), 2)], pairs);
+}
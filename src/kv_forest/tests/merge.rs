@@ -0,0 +1,70 @@
+use std::fs;
+
+use crate::kv_forest::diff::Diff;
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn merge_applies_both_sides_and_resolves_conflicts() {
+	let path = prepare_kv_store_test_dir("merge-three-way");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let base = forest.build_from_sorted((0..100).map(|i| (i, i))).expect("build");
+	let mut ours = forest.batch(base).expect("batch");
+	ours.push(1, 1000).expect("push");
+	ours.push(5, 55).expect("push");
+	ours.push(200, 200).expect("push");
+	ours.remove(&2).expect("remove");
+	let ours = ours.commit().expect("commit");
+	let mut theirs = forest.batch(base).expect("batch");
+	theirs.push(1, 2000).expect("push");
+	theirs.push(3, 33).expect("push");
+	theirs.push(5, 55).expect("push");
+	theirs.push(300, 300).expect("push");
+	theirs.remove(&4).expect("remove");
+	let theirs = theirs.commit().expect("commit");
+
+	let mut conflicts = Vec::new();
+	let merged = forest.merge(base, ours, theirs, |key, base, ours, theirs| {
+		conflicts.push((*key, base.copied(), ours.copied(), theirs.copied()));
+		ours.max(theirs).copied()
+	}).expect("merge");
+	assert_eq!(vec![(1, Some(1), Some(1000), Some(2000))], conflicts);
+	let expected = [(1, Some(2000)), (2, None), (3, Some(33)), (4, None), (5, Some(55)), (6, Some(6)), (200, Some(200)), (300, Some(300))];
+	for (key, value) in expected {
+		assert_eq!(value, forest.find(merged, &key).expect("find"), "key {}", key);
+	}
+	assert_eq!(100, forest.iter(merged).expect("iter").count());
+}
+
+#[test]
+fn merge_without_their_changes_keeps_our_root() {
+	let path = prepare_kv_store_test_dir("merge-noop");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let base = forest.push(forest.latest_root(), 1, 1).expect("push");
+	let ours = forest.push(base, 2, 2).expect("push");
+	let merged = forest.merge(base, ours, base, |_, _, _, _| panic!("no conflicts")).expect("merge");
+	assert_eq!(ours, merged);
+}
+
+#[test]
+fn merge_grafts_their_sub_tries_without_rewriting_values() {
+	let path = prepare_kv_store_test_dir("merge-graft");
+	let forest_path = path.join("forest");
+	let mut forest = KvForest::<u32, String>::open(&forest_path).expect("open or create");
+	let base = forest.build_from_sorted((0..1000).map(|i| (i, format!("base-{}", i)))).expect("build");
+	let ours = forest.push(base, 0xf000_0000, "ours".to_string()).expect("push");
+	let mut theirs = forest.batch(base).expect("batch");
+	for i in 0..50 {
+		theirs.push(i * 7, format!("theirs-{}", i)).expect("push");
+	}
+	let theirs = theirs.commit().expect("commit");
+
+	let elements_path = forest_path.join("elements.stash").join("elements.store");
+	let elements_before = fs::metadata(&elements_path).expect("metadata").len();
+	let values_before = fs::metadata(forest_path.join("values.stash")).expect("metadata").len();
+	let merged = forest.merge(base, ours, theirs, |_, _, _, _| panic!("no conflicts")).expect("merge");
+	assert_eq!(values_before, fs::metadata(forest_path.join("values.stash")).expect("metadata").len());
+	assert!(fs::metadata(&elements_path).expect("metadata").len() - elements_before <= 16 * 8);
+	assert_eq!(vec![Diff::Added(0xf000_0000, "ours".to_string())], forest.diff(theirs, merged).expect("diff"));
+	assert_eq!(Some("theirs-3".to_string()), forest.find(merged, &21).expect("find"));
+}
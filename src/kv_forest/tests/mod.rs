@@ -4,8 +4,21 @@ use crate::tests::ready_test_dir;
 
 use super::*;
 
+mod batch;
+mod build;
+mod commit;
+mod compact;
+mod diff;
+mod history;
 mod insertion;
+mod iteration;
+mod merge;
 mod persistence;
+mod prefix;
+mod range;
+mod refs;
+mod removal;
+mod values;
 
 mod basic {
 	use crate::kv_forest::KvForest;
@@ -26,7 +39,7 @@ mod basic {
 		let index = forest.add_root()?;
 		let key = "Hey".to_string();
 		let index = forest.push(index, key.clone(), 10)?;
-		let read = forest.find(index, &key);
+		let read = forest.find(index, &key)?;
 		assert_eq!(Some(10), read);
 		Ok(())
 	}
@@ -1,5 +1,10 @@
-use crate::kv_forest::KvForest;
+use std::fs;
+
+use crate::Error;
+use crate::header::FileKind;
+use crate::kv_forest::{KvForest, RootIndex};
 use crate::kv_forest::tests::prepare_kv_store_test_dir;
+use crate::verify::check_forest;
 
 #[test]
 fn persist_empty() {
@@ -28,6 +33,83 @@ fn persist_thousand_internal_71() {
 	let trie = forest.trie(index).expect("trie at index");
 	assert_eq!(1000, trie.size());
 	for i in 0..1000 {
-		assert_eq!(Some(i + 1), forest.find(index, &(i * 71)));
+		assert_eq!(Some(i + 1), forest.find(index, &(i * 71)).expect("find"));
+	}
+}
+
+#[test]
+fn persist_high_keys() {
+	let path = prepare_kv_store_test_dir("persist-high-keys");
+	let keys = [0x8000_0000u32, 0xdead_beef, u32::MAX];
+	let index = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let mut index = forest.add_root().expect("add-root");
+		for key in keys {
+			index = forest.push(index, key, key / 2).expect("push");
+		}
+		index
+	};
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	for key in keys {
+		assert_eq!(Some(key / 2), forest.find(index, &key).expect("find"));
+	}
+}
+
+#[test]
+fn missing_root_is_an_error() {
+	let path = prepare_kv_store_test_dir("persist-missing-root");
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let error = forest.find(RootIndex::from(1000), &1).expect_err("missing root");
+	assert!(matches!(error, Error::BadRootIndex(_)));
+}
+
+#[test]
+fn open_rejects_forest_with_other_key_type() {
+	let path = prepare_kv_store_test_dir("persist-wrong-key-kind");
+	KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let error = KvForest::<String>::open(path.join("forest")).err().expect("wrong kind");
+	assert!(matches!(error, Error::WrongFileKind { expected: FileKind::StringKeys, found: FileKind::U32Keys }));
+}
+
+#[test]
+fn repeated_string_keys_are_stored_once() {
+	let path = prepare_kv_store_test_dir("interned-keys");
+	let forest_path = path.join("forest");
+	let keys_path = forest_path.join("keys.stash");
+	let mut forest = KvForest::<String>::open(&forest_path).expect("open or create");
+	let mut index = forest.latest_root();
+	index = forest.push(index, "repeated".to_string(), 0).expect("push");
+	forest.commit(index).expect("commit");
+	let keys_length = fs::metadata(&keys_path).expect("metadata").len();
+	for value in 1..100 {
+		index = forest.push(index, "repeated".to_string(), value).expect("push");
 	}
-}
\ No newline at end of file
+	forest.commit(index).expect("commit");
+	drop(forest);
+	let mut forest = KvForest::<String>::open(&forest_path).expect("reopen");
+	let index = forest.push(index, "repeated".to_string(), 100).expect("push");
+	assert_eq!(Some(100), forest.find(index, &"repeated".to_string()).expect("find"));
+	assert_eq!(keys_length, fs::metadata(&keys_path).expect("metadata").len());
+}
+
+#[test]
+fn byte_keys_persist() {
+	let path = prepare_kv_store_test_dir("byte-keys");
+	let forest_path = path.join("forest");
+	let index = {
+		let mut forest = KvForest::<Vec<u8>>::open(&forest_path).expect("open or create");
+		let mut index = forest.latest_root();
+		for i in 0..=255u8 {
+			index = forest.push(index, vec![0xff, i, 0x00], i as u32).expect("push");
+		}
+		forest.commit(index).expect("commit");
+		index
+	};
+	let forest = KvForest::<Vec<u8>>::open(&forest_path).expect("reopen");
+	assert_eq!(Some(0x80), forest.find(index, &vec![0xff, 0x80, 0x00]).expect("find"));
+	assert_eq!(None, forest.find(index, &vec![0xff, 0x80]).expect("find prefix"));
+	drop(forest);
+	let forest = KvForest::<[u8; 3]>::open(&forest_path).expect("reopen as arrays");
+	assert_eq!(Some(7), forest.find(index, &[0xff, 7, 0x00]).expect("find array"));
+	assert!(check_forest(&forest_path).expect("check").is_ok());
+}
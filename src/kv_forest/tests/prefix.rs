@@ -0,0 +1,35 @@
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn scan_prefix_yields_only_matching_keys() {
+	let path = prepare_kv_store_test_dir("prefix-matching");
+	let mut forest = KvForest::<String>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	let keys = ["user/alice", "user/bob", "user/carol", "users-count", "group/admins", "group/users"];
+	for (value, key) in keys.iter().enumerate() {
+		index = forest.push(index, key.to_string(), value as u32).expect("push");
+	}
+	let mut found = forest.scan_prefix(index, "user/").expect("scan").collect::<crate::Result<Vec<_>>>().expect("pairs");
+	found.sort();
+	assert_eq!(
+		vec![("user/alice".to_string(), 0), ("user/bob".to_string(), 1), ("user/carol".to_string(), 2)],
+		found
+	);
+	let found = forest.scan_prefix(index, "group/").expect("scan").count();
+	assert_eq!(2, found);
+	let found = forest.scan_prefix(index, "").expect("scan").count();
+	assert_eq!(keys.len(), found);
+}
+
+#[test]
+fn scan_prefix_finds_shallow_key() {
+	let path = prepare_kv_store_test_dir("prefix-shallow");
+	let mut forest = KvForest::<String>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	let index = forest.push(index, "user/alice".to_string(), 1).expect("push");
+	let index = forest.push(index, "group/admins".to_string(), 2).expect("push");
+	let found = forest.scan_prefix(index, "user/").expect("scan").collect::<crate::Result<Vec<_>>>().expect("pairs");
+	assert_eq!(vec![("user/alice".to_string(), 1)], found);
+	assert_eq!(0, forest.scan_prefix(index, "team/").expect("scan").count());
+}
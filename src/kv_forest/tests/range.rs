@@ -0,0 +1,98 @@
+use std::ops::Bound;
+
+use crate::kv_forest::{KvForest, RootIndex};
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+use crate::verify::check_forest;
+
+fn forest_with_keys(name: &str, keys: impl IntoIterator<Item=u32>) -> (KvForest<u32>, RootIndex) {
+	let path = prepare_kv_store_test_dir(name);
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	for key in keys {
+		index = forest.push(index, key, key + 1).expect("push");
+	}
+	(forest, index)
+}
+
+#[test]
+fn range_yields_keys_in_ascending_order() {
+	let keys = (0..1000).map(|i| (i * 7919) % 1000 * 71).collect::<Vec<_>>();
+	let (forest, index) = forest_with_keys("range-ascending", keys);
+	let found = forest.range(index, ..).expect("range").collect::<crate::Result<Vec<_>>>().expect("pairs");
+	let expected = (0..1000).map(|i| (i * 71, i * 71 + 1)).collect::<Vec<_>>();
+	assert_eq!(expected, found);
+}
+
+#[test]
+fn range_respects_bounds() {
+	let (forest, index) = forest_with_keys("range-bounds", (0..1000).map(|i| i * 71));
+	let found = forest.range(index, 710..1420).expect("range").map(|entry| entry.map(|(key, _)| key)).collect::<crate::Result<Vec<_>>>().expect("keys");
+	assert_eq!((10..20).map(|i| i * 71).collect::<Vec<_>>(), found);
+	let found = forest.range(index, 700..=710).expect("range").map(|entry| entry.map(|(key, _)| key)).collect::<crate::Result<Vec<_>>>().expect("keys");
+	assert_eq!(vec![710], found);
+	assert_eq!(0, forest.range(index, 1..71).expect("range").count());
+	assert_eq!(0, forest.range(index, 71..71).expect("empty range").count());
+	assert_eq!(0, forest.range(RootIndex::from(u32::MAX - 1), (Bound::Excluded(5), Bound::Included(5))).expect("empty range skips the root").count());
+}
+
+#[test]
+fn min_max_and_first_after() {
+	let (forest, index) = forest_with_keys("range-min-max", [5, 0x4000_0000, 17, 0x1234_5678, 1 << 20]);
+	assert_eq!(Some((5, 6)), forest.min(index).expect("min"));
+	assert_eq!(Some((0x4000_0000, 0x4000_0001)), forest.max(index).expect("max"));
+	assert_eq!(Some((17, 18)), forest.first_after(index, 5).expect("first after"));
+	assert_eq!(Some((1 << 20, (1 << 20) + 1)), forest.first_after(index, 17).expect("first after"));
+	assert_eq!(None, forest.first_after(index, 0x4000_0000).expect("first after"));
+}
+
+#[test]
+fn min_max_of_empty_root() {
+	let (forest, index) = forest_with_keys("range-empty", []);
+	assert_eq!(None, forest.min(index).expect("min"));
+	assert_eq!(None, forest.max(index).expect("max"));
+}
+
+#[test]
+fn signed_keys_range_in_numeric_order() {
+	let path = prepare_kv_store_test_dir("range-signed");
+	let mut forest = KvForest::<i64>::open(path.join("forest")).expect("open or create");
+	let keys = [i64::MIN, -(1 << 40), -70_000, -1, 0, 1, 70_000, 1 << 40, i64::MAX];
+	let mut index = forest.add_root().expect("index");
+	for (value, key) in keys.iter().enumerate().rev() {
+		index = forest.push(index, *key, value as u32).expect("push");
+	}
+	let found = forest.range(index, ..).expect("range").map(|entry| entry.map(|(key, _)| key)).collect::<crate::Result<Vec<_>>>().expect("keys");
+	assert_eq!(keys.to_vec(), found);
+	let found = forest.range(index, -70_000..1).expect("range").map(|entry| entry.map(|(key, _)| key)).collect::<crate::Result<Vec<_>>>().expect("keys");
+	assert_eq!(vec![-70_000, -1, 0], found);
+	assert_eq!(Some((i64::MIN, 0)), forest.min(index).expect("min"));
+	assert_eq!(Some((i64::MAX, 8)), forest.max(index).expect("max"));
+	assert_eq!(Some((0, 4)), forest.first_after(index, -1).expect("first after"));
+	assert_eq!(None, forest.first_after(index, i64::MAX).expect("first after"));
+}
+
+#[test]
+fn wide_and_narrow_keys_persist() {
+	let path = prepare_kv_store_test_dir("range-wide-narrow");
+	let index = {
+		let mut forest = KvForest::<u64>::open(path.join("wide")).expect("open or create");
+		let mut index = forest.add_root().expect("index");
+		for key in [u64::MAX, 1 << 33, 5] {
+			index = forest.push(index, key, (key % 1000) as u32).expect("push");
+		}
+		forest.commit(index).expect("commit");
+		index
+	};
+	let forest = KvForest::<u64>::open(path.join("wide")).expect("reopen");
+	let found = forest.range(index, 6..).expect("range").map(|entry| entry.map(|(key, _)| key)).collect::<crate::Result<Vec<_>>>().expect("keys");
+	assert_eq!(vec![1 << 33, u64::MAX], found);
+	assert!(check_forest(path.join("wide")).expect("check").is_ok());
+
+	let mut forest = KvForest::<i32>::open(path.join("narrow")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	for key in [i32::MAX, 3, i32::MIN, -3] {
+		index = forest.push(index, key, 0).expect("push");
+	}
+	let found = forest.range(index, ..=3).expect("range").map(|entry| entry.map(|(key, _)| key)).collect::<crate::Result<Vec<_>>>().expect("keys");
+	assert_eq!(vec![i32::MIN, -3, 3], found);
+}
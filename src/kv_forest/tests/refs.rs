@@ -0,0 +1,88 @@
+use std::fs;
+
+use crate::Error;
+use crate::header::{FileHeader, FileKind, HEADER_BYTES};
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn refs_survive_reopen() {
+	let path = prepare_kv_store_test_dir("refs-reopen");
+	let (main, dev) = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let main = forest.push(forest.latest_root(), 1, 10).expect("push");
+		let dev = forest.push(main, 2, 20).expect("push");
+		forest.set_ref("main", main).expect("set main");
+		forest.set_ref("dev", dev).expect("set dev");
+		forest.set_ref("scratch", dev).expect("set scratch");
+		assert_eq!(Some(dev), forest.delete_ref("scratch").expect("delete"));
+		assert_eq!(None, forest.delete_ref("scratch").expect("delete again"));
+		(main, dev)
+	};
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(vec![("dev".to_string(), dev), ("main".to_string(), main)], forest.list_refs());
+	assert_eq!(Some(main), forest.get_ref("main"));
+	assert_eq!(None, forest.get_ref("scratch"));
+	assert_eq!(None, forest.find(main, &2).expect("find"));
+	assert_eq!(Some(20), forest.find(dev, &2).expect("find"));
+}
+
+#[test]
+fn ref_updates_leave_latest_root_alone() {
+	let path = prepare_kv_store_test_dir("refs-latest");
+	let committed = {
+		let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+		let committed = forest.push(forest.latest_root(), 1, 10).expect("push");
+		forest.commit(committed).expect("commit");
+		let side = forest.push(committed, 2, 20).expect("push");
+		forest.set_ref("side", side).expect("set side");
+		let other = forest.push(committed, 3, 30).expect("push");
+		forest.compare_and_set_ref("other", None, Some(other)).expect("create other");
+		assert_eq!(committed, forest.latest_root());
+		let next = forest.push(committed, 4, 40).expect("push");
+		forest.commit(next).expect("commit");
+		assert_eq!(Some(committed), forest.commit_log.latest().and_then(|record| record.parent));
+		committed
+	};
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(Some(40), forest.find(forest.latest_root(), &4).expect("find"));
+	assert_eq!(Some(10), forest.find(forest.latest_root(), &1).expect("find"));
+	assert_eq!(None, forest.find(forest.latest_root(), &2).expect("find"));
+	assert_ne!(committed, forest.latest_root());
+	let bytes = fs::read(path.join("forest").join("refs.json")).expect("read refs");
+	assert_eq!(FileHeader::current(FileKind::Refs).to_bytes(), bytes[0..HEADER_BYTES as usize]);
+}
+
+#[test]
+fn compare_and_set_rejects_stale_expectation() {
+	let path = prepare_kv_store_test_dir("refs-cas");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let first = forest.push(forest.latest_root(), 1, 10).expect("push");
+	let second = forest.push(first, 1, 11).expect("push");
+	forest.compare_and_set_ref("main", None, Some(first)).expect("create main");
+	let error = forest.compare_and_set_ref("main", None, Some(second)).expect_err("stale");
+	assert!(matches!(error, Error::RefConflict { found: Some(found), .. } if found == first));
+	forest.compare_and_set_ref("main", Some(first), Some(second)).expect("advance main");
+	assert_eq!(Some(second), forest.get_ref("main"));
+	forest.compare_and_set_ref("main", Some(second), None).expect("delete main");
+	assert!(forest.list_refs().is_empty());
+}
+
+#[test]
+fn compact_keeps_and_remaps_refs() {
+	let path = prepare_kv_store_test_dir("refs-compact");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.latest_root();
+	for i in 0..100 {
+		index = forest.push(index, i, i).expect("push");
+	}
+	forest.set_ref("old", index).expect("set old");
+	let latest = forest.push(forest.latest_root(), 1000, 1).expect("push");
+	forest.commit(latest).expect("commit");
+	let remapped = forest.compact(&[]).expect("compact");
+	let old = forest.get_ref("old").expect("old ref");
+	assert_eq!(remapped[&index], old);
+	assert_eq!(Some(99), forest.find(old, &99).expect("find"));
+	let forest = KvForest::<u32>::open(path.join("forest")).expect("reopen");
+	assert_eq!(Some(old), forest.get_ref("old"));
+}
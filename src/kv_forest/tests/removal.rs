@@ -0,0 +1,60 @@
+use crate::kv_forest::KvForest;
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+use crate::trie::Element;
+
+#[test]
+fn remove_key_keeps_other_values() {
+	let path = prepare_kv_store_test_dir("remove-a");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	let index = forest.push(index, 0b000000000100000, 1).expect("push");
+	let index = forest.push(index, 0b000000001000000, 2).expect("push");
+	let index = forest.push(index, 0b000000001000001, 3).expect("push");
+	let index = forest.remove(index, &0b000000001000000).expect("remove");
+	let trie = forest.trie(index).expect("trie");
+	assert_eq!(2, trie.size());
+	assert_eq!(Some(1), forest.find(index, &0b000000000100000).expect("find"));
+	assert_eq!(None, forest.find(index, &0b000000001000000).expect("find"));
+	assert_eq!(Some(3), forest.find(index, &0b000000001000001).expect("find"));
+}
+
+#[test]
+fn remove_key_collapses_zipped_sub_tries() {
+	let path = prepare_kv_store_test_dir("remove-b");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	let index = forest.push(index, 0b000000000100000, 1).expect("push");
+	let index = forest.push(index, 0b000000001000000, 2).expect("push");
+	let index = forest.remove(index, &0b000000000100000).expect("remove");
+	let trie = forest.trie(index).expect("trie");
+	assert_eq!(1, trie.elements.len());
+	assert!(matches!(trie.elements[0], Element::KeyValue { value: 2, .. }));
+	assert_eq!(Some(2), forest.find(index, &0b000000001000000).expect("find"));
+}
+
+#[test]
+fn remove_missing_key_keeps_root() {
+	let path = prepare_kv_store_test_dir("remove-c");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	let index = forest.push(index, 0b000000000100000, 1).expect("push");
+	let index = forest.push(index, 0b000000001000000, 2).expect("push");
+	let same_index = forest.remove(index, &0b000000001000001).expect("remove");
+	assert_eq!(index, same_index);
+}
+
+#[test]
+fn remove_all_keys_leaves_empty_root() {
+	let path = prepare_kv_store_test_dir("remove-d");
+	let mut forest = KvForest::<u32>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	for i in 0..100 {
+		index = forest.push(index, i * 71, i + 1).expect("push");
+	}
+	for i in 0..100 {
+		index = forest.remove(index, &(i * 71)).expect("remove");
+		assert_eq!(None, forest.find(index, &(i * 71)).expect("find"));
+	}
+	let trie = forest.trie(index).expect("trie");
+	assert_eq!(0, trie.size());
+}
@@ -0,0 +1,42 @@
+use crate::kv_forest::{KvForest, RootIndex};
+use crate::kv_forest::tests::prepare_kv_store_test_dir;
+
+#[test]
+fn inline_root_index_values() {
+	let path = prepare_kv_store_test_dir("values-inline");
+	let mut forest = KvForest::<u32, RootIndex>::open(path.join("forest")).expect("open or create");
+	let index = forest.add_root().expect("index");
+	let index = forest.push(index, 3, RootIndex::from(17)).expect("push");
+	assert_eq!(Some(RootIndex::from(17)), forest.find(index, &3).expect("find"));
+	assert!(!path.join("forest").join("values.stash").exists());
+}
+
+#[test]
+fn stored_string_values_survive_reopen() {
+	let path = prepare_kv_store_test_dir("values-stored");
+	let index = {
+		let mut forest = KvForest::<String, String>::open(path.join("forest")).expect("open or create");
+		let index = forest.add_root().expect("index");
+		let index = forest.push(index, "greeting".to_string(), "Hello, world!".to_string()).expect("push");
+		let index = forest.push(index, "empty".to_string(), String::new()).expect("push");
+		forest.push(index, "greeting".to_string(), "Bye".to_string()).expect("push")
+	};
+	let forest = KvForest::<String, String>::open(path.join("forest")).expect("open or create");
+	assert_eq!(Some("Bye".to_string()), forest.find(index, &"greeting".to_string()).expect("find"));
+	assert_eq!(Some(String::new()), forest.find(index, &"empty".to_string()).expect("find"));
+	let mut pairs = forest.iter(index).expect("iter").collect::<crate::Result<Vec<_>>>().expect("pairs");
+	pairs.sort();
+	assert_eq!(vec![("empty".to_string(), String::new()), ("greeting".to_string(), "Bye".to_string())], pairs);
+}
+
+#[test]
+fn stored_u64_values_in_range() {
+	let path = prepare_kv_store_test_dir("values-u64");
+	let mut forest = KvForest::<u32, u64>::open(path.join("forest")).expect("open or create");
+	let mut index = forest.add_root().expect("index");
+	for i in 0..10u32 {
+		index = forest.push(index, i, u64::MAX - i as u64).expect("push");
+	}
+	assert_eq!(Some((9, u64::MAX - 9)), forest.max(index).expect("max"));
+	assert_eq!(Some((4, u64::MAX - 4)), forest.first_after(index, 3).expect("first after"));
+}
@@ -1,9 +1,16 @@
+mod checksum;
 pub mod datom;
+pub mod error;
+pub mod header;
 pub mod item_stash;
 pub mod key_store;
 pub mod kv_forest;
 pub mod trie;
+pub mod value_store;
+pub mod verify;
 pub mod db;
 
+pub use error::{Error, Result};
+
 #[cfg(test)]
 pub mod tests;
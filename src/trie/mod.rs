@@ -1,9 +1,9 @@
 use std::cell::OnceCell;
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::io;
 use std::ops::Index;
 use std::rc::Rc;
 
+use crate::error::Result;
 use crate::item_stash::element::ElementStoreIndex;
 use crate::item_stash::element_read::{ElementRead, SavedElementList};
 use crate::key_store::{Key, KeyStore, ReadKey};
@@ -48,21 +48,21 @@ impl Trie {
 		let right = self.map.0;
 		[left, right]
 	}
-	pub fn find<K: Key>(&self, search_key: &K, read_key: &impl ReadKey<K>) -> Option<&u32> {
+	pub fn find<K: Key>(&self, search_key: &K, read_key: &impl ReadKey<K>) -> Result<Option<&u32>> {
 		let mut depth = 0;
 		let mut active_trie = self;
 		loop {
 			let key_byte = search_key.to_shard(depth);
 			match active_trie.map.to_viewing_index(key_byte) {
 				None => {
-					return None;
+					return Ok(None);
 				}
 				Some(viewing_index) => {
-					let element = active_trie.elements.try_get(viewing_index).expect("get element");
+					let element = active_trie.elements.try_get(viewing_index)?;
 					match element {
 						Element::KeyValue { key, value } => {
-							let saved_key = read_key.read_key(KeyStoreIndex::from(key)).expect("read key");
-							return (&saved_key == search_key).then_some(value);
+							let saved_key = read_key.read_key(KeyStoreIndex::from(key))?;
+							return Ok((&saved_key == search_key).then_some(value));
 						}
 						Element::SubTrie(trie) => {
 							active_trie = trie;
@@ -73,8 +73,8 @@ impl Trie {
 			}
 		}
 	}
-	pub fn push<K: Key>(&self, insert_key: K, insert_value: u32, key_store: &mut impl KeyStore<K>) -> Self {
-		let insert_key_index = key_store.write_key(&insert_key).expect("write key");
+	pub fn push<K: Key>(&self, insert_key: K, insert_value: u32, key_store: &mut impl KeyStore<K>) -> Result<Self> {
+		let insert_key_index = key_store.write_key(&insert_key)?;
 		let mut back_trie: Trie;
 		let mut back_tasks = Vec::new();
 		{
@@ -86,17 +86,17 @@ impl Trie {
 				match viewing_index {
 					None => {
 						let element = Element::KeyValue { key: KeyField::from(insert_key_index), value: insert_value.clone() };
-						back_trie = active_trie.insert_or_replace_element(key_byte, element);
+						back_trie = active_trie.insert_or_replace_element(key_byte, element)?;
 						break;
 					}
 					Some(viewing_index) => {
-						match active_trie.elements.try_get(viewing_index).expect("get element") {
+						match active_trie.elements.try_get(viewing_index)? {
 							Element::KeyValue { key: old_key_field, value: old_value } => {
 								let old_key_index = KeyStoreIndex::from(old_key_field);
-								let old_key = key_store.read_key(old_key_index).expect("read key");
+								let old_key = key_store.read_key(old_key_index)?;
 								if old_key == insert_key {
 									let replacement = Element::KeyValue { key: KeyField::from(insert_key_index), value: insert_value };
-									back_trie = active_trie.insert_or_replace_element(key_byte, replacement);
+									back_trie = active_trie.insert_or_replace_element(key_byte, replacement)?;
 									break;
 								} else {
 									let replacement = {
@@ -107,7 +107,7 @@ impl Trie {
 										);
 										Element::SubTrie(zipped_trie)
 									};
-									back_trie = active_trie.insert_or_replace_element(key_byte, replacement);
+									back_trie = active_trie.insert_or_replace_element(key_byte, replacement)?;
 									break;
 								}
 							}
@@ -123,9 +123,62 @@ impl Trie {
 		}
 		while let Some((key_byte, trie)) = back_tasks.pop() {
 			let element = Element::SubTrie(back_trie);
-			back_trie = trie.insert_or_replace_element(key_byte, element);
+			back_trie = trie.insert_or_replace_element(key_byte, element)?;
 		}
-		back_trie
+		Ok(back_trie)
+	}
+
+	pub fn remove<K: Key>(&self, remove_key: &K, read_key: &impl ReadKey<K>) -> Result<Self> {
+		let mut back_trie: Trie;
+		let mut back_tasks = Vec::new();
+		{
+			let mut active_depth = 0;
+			let mut active_trie = self;
+			loop {
+				let key_byte = remove_key.to_shard(active_depth);
+				match active_trie.map.to_viewing_index(key_byte) {
+					None => {
+						return Ok(self.clone());
+					}
+					Some(viewing_index) => {
+						match active_trie.elements.try_get(viewing_index)? {
+							Element::KeyValue { key, .. } => {
+								let saved_key = read_key.read_key(KeyStoreIndex::from(key))?;
+								if &saved_key != remove_key {
+									return Ok(self.clone());
+								}
+								back_trie = active_trie.remove_element(key_byte)?;
+								break;
+							}
+							Element::SubTrie(sub_trie) => {
+								back_tasks.push((key_byte, active_trie));
+								active_trie = sub_trie;
+								active_depth += 1;
+							}
+						}
+					}
+				}
+			}
+		}
+		while let Some((key_byte, trie)) = back_tasks.pop() {
+			back_trie = match back_trie.to_lifted_element()? {
+				Some(element) => trie.insert_or_replace_element(key_byte, element)?,
+				None if back_trie.elements.len() == 0 => trie.remove_element(key_byte)?,
+				None => trie.insert_or_replace_element(key_byte, Element::SubTrie(back_trie))?,
+			};
+		}
+		Ok(back_trie)
+	}
+
+	pub(crate) fn to_lifted_element(&self) -> Result<Option<Element>> {
+		if self.elements.len() != 1 {
+			return Ok(None);
+		}
+		let lifted = match self.elements.try_get(0)? {
+			Element::KeyValue { key, value } => Some(Element::KeyValue { key: *key, value: *value }),
+			Element::SubTrie(_) => None,
+		};
+		Ok(lifted)
 	}
 
 	fn zip_values<K: Key>(
@@ -133,6 +186,7 @@ impl Trie {
 		(key1, key1_index, value1): (&K, &KeyStoreIndex, &u32),
 		(key2, key2_index, value2): (K, KeyStoreIndex, u32),
 	) -> Self {
+		debug_assert!(key1 != &key2, "zip distinct keys");
 		let mut depth = start_depth;
 		let mut back_trie: Self;
 		let mut back_tasks = Vec::new();
@@ -150,9 +204,9 @@ impl Trie {
 						value: value2,
 					};
 					let element_list = if key1_byte < key2_byte {
-						DirectElementList::empty().insert(0, key1_element).insert(1, key2_element)
+						DirectElementList(vec![key1_element, key2_element])
 					} else {
-						DirectElementList::empty().insert(0, key2_element).insert(1, key1_element)
+						DirectElementList(vec![key2_element, key1_element])
 					};
 					ElementData::Direct(element_list)
 				};
@@ -167,7 +221,7 @@ impl Trie {
 			let map = ElementMap::just_key(key_byte);
 			let elements = {
 				let key_element = Element::SubTrie(back_trie);
-				let element_list = DirectElementList::empty().insert(0, key_element);
+				let element_list = DirectElementList(vec![key_element]);
 				ElementData::Direct(element_list)
 			};
 			back_trie = Self { map, elements };
@@ -175,18 +229,29 @@ impl Trie {
 		back_trie
 	}
 
-	fn insert_or_replace_element(&self, key_byte: u8, element: Element) -> Self {
+	pub(crate) fn remove_element(&self, key_byte: u8) -> Result<Self> {
+		match self.map.to_viewing_index(key_byte) {
+			None => Ok(self.clone()),
+			Some(index) => {
+				let elements = self.elements.remove(index)?;
+				let map = self.map.exclude_key(key_byte);
+				Ok(Self { map, elements })
+			}
+		}
+	}
+
+	pub(crate) fn insert_or_replace_element(&self, key_byte: u8, element: Element) -> Result<Self> {
 		match self.map.to_viewing_index(key_byte) {
 			None => {
 				let insertion_index = self.map.to_insertion_index(key_byte);
-				let elements = self.elements.insert(insertion_index, element);
+				let elements = self.elements.insert(insertion_index, element)?;
 				let map = self.map.include_key(key_byte);
-				Self { map, elements }
+				Ok(Self { map, elements })
 			}
 			Some(index) => {
-				let elements = self.elements.replace(index, element);
+				let elements = self.elements.replace(index, element)?;
 				let map = self.map.clone();
-				Self { map, elements }
+				Ok(Self { map, elements })
 			}
 		}
 	}
@@ -211,6 +276,17 @@ impl Trie {
 		count
 	}
 
+	pub fn entries(&self) -> TrieEntries {
+		self.entries_within(|_| true)
+	}
+	pub fn entries_within(&self, visit_shards: impl Fn(&[u8]) -> bool + 'static) -> TrieEntries {
+		TrieEntries {
+			stack: vec![(self.clone(), 0)],
+			shards: Vec::new(),
+			visit_shards: Box::new(visit_shards),
+		}
+	}
+
 	pub fn new() -> Self {
 		let map = ElementMap::empty();
 		let elements = ElementData::empty();
@@ -218,30 +294,80 @@ impl Trie {
 	}
 }
 
+type VisitShards = Box<dyn Fn(&[u8]) -> bool>;
+
+pub struct TrieEntries {
+	stack: Vec<(Trie, usize)>,
+	shards: Vec<u8>,
+	visit_shards: VisitShards,
+}
+
+impl Iterator for TrieEntries {
+	type Item = Result<(KeyField, u32)>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let (trie, next_index) = self.stack.last_mut()?;
+			if *next_index >= trie.elements.len() {
+				self.stack.pop();
+				self.shards.pop();
+				continue;
+			}
+			let element_index = *next_index;
+			*next_index += 1;
+			self.shards.push(trie.map.to_key(element_index));
+			if !(self.visit_shards)(&self.shards) {
+				self.shards.pop();
+				continue;
+			}
+			match trie.elements.try_get(element_index) {
+				Ok(Element::SubTrie(sub_trie)) => {
+					let sub_trie = sub_trie.clone();
+					self.stack.push((sub_trie, 0));
+				}
+				Ok(Element::KeyValue { key, value }) => {
+					let entry = (*key, *value);
+					self.shards.pop();
+					return Some(Ok(entry));
+				}
+				Err(error) => {
+					self.shards.pop();
+					return Some(Err(error));
+				}
+			}
+		}
+	}
+}
+
 pub trait ElementList {
-	fn insert(&self, index: usize, element: Element) -> DirectElementList {
-		let mut new_elements = self.to_elements();
+	fn insert(&self, index: usize, element: Element) -> Result<DirectElementList> {
+		let mut new_elements = self.to_elements()?;
 		new_elements.insert(index, element);
-		DirectElementList(new_elements)
+		Ok(DirectElementList(new_elements))
 	}
-	fn replace(&self, index: usize, element: Element) -> DirectElementList {
-		let mut new_elements = self.to_elements();
+	fn replace(&self, index: usize, element: Element) -> Result<DirectElementList> {
+		let mut new_elements = self.to_elements()?;
 		new_elements.remove(index);
 		new_elements.insert(index, element);
-		DirectElementList(new_elements)
+		Ok(DirectElementList(new_elements))
+	}
+	fn remove(&self, index: usize) -> Result<DirectElementList> {
+		let mut new_elements = self.to_elements()?;
+		new_elements.remove(index);
+		Ok(DirectElementList(new_elements))
 	}
-	fn to_elements(&self) -> Vec<Element>;
+	fn to_elements(&self) -> Result<Vec<Element>>;
 
-	fn try_get(&self, index: usize) -> io::Result<&Element>;
+	fn try_get(&self, index: usize) -> Result<&Element>;
 }
 
 #[derive(Debug, Clone, Hash)]
 pub struct DirectElementList(pub Vec<Element>);
 
 impl ElementList for DirectElementList {
-	fn to_elements(&self) -> Vec<Element> { self.0.clone() }
+	fn to_elements(&self) -> Result<Vec<Element>> { Ok(self.0.clone()) }
 
-	fn try_get(&self, index: usize) -> io::Result<&Element> {
+	fn try_get(&self, index: usize) -> Result<&Element> {
 		Ok(&self[index])
 	}
 }
@@ -299,6 +425,27 @@ pub fn u32_key_byte(value: &u32, depth: usize) -> u8 {
 	shifted & 0b11111
 }
 
+fn ordered_shard_shift(key_bits: u32, depth: usize) -> u32 {
+	let levels = key_bits.div_ceil(5) as usize;
+	5 * (levels - 1 - depth % levels) as u32
+}
+
+pub fn ordered_key_shard(bits: u64, key_bits: u32, depth: usize) -> u8 {
+	((bits >> ordered_shard_shift(key_bits, depth)) & 0b11111) as u8
+}
+
+pub fn ordered_shard_bounds(shards: &[u8], key_bits: u32) -> (u64, u64) {
+	let levels = key_bits.div_ceil(5) as usize;
+	let mut low = 0u64;
+	let mut free_bits = key_bits;
+	for (depth, shard) in shards.iter().enumerate().take(levels) {
+		free_bits = ordered_shard_shift(key_bits, depth);
+		low |= (*shard as u64) << free_bits;
+	}
+	let high = low | u64::MAX.checked_shr(64 - free_bits).unwrap_or(0);
+	(low, high)
+}
+
 pub fn u32_is_stash_index(value: u32) -> bool {
 	(value & 0x80000000) == 0
 }
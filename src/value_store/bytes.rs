@@ -0,0 +1,146 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::header::{check_file, create_file, FileKind, HEADER_BYTES};
+use crate::value_store::{ReadValue, Value, ValueField, ValueStore};
+
+pub trait BytesValue: Sized + 'static {
+	fn to_value_bytes(&self) -> Vec<u8>;
+	fn from_value_bytes(bytes: Vec<u8>) -> Result<Self>;
+}
+
+pub struct BytesValueStore<V: BytesValue> {
+	file: File,
+	phantom: PhantomData<V>,
+}
+
+impl<V: BytesValue> BytesValueStore<V> {
+	pub fn open(store_path: impl AsRef<Path>) -> Result<Self> {
+		let path = store_path.as_ref();
+		if !path.exists() {
+			create_file(path, FileKind::Values)?;
+		}
+		let file = OpenOptions::new().read(true).append(true).open(path)?;
+		check_file(&file, FileKind::Values)?;
+		Ok(Self { file, phantom: PhantomData })
+	}
+}
+
+impl<V: BytesValue> ReadValue<V> for BytesValueStore<V> {
+	fn read_value(&self, field: ValueField) -> Result<V> {
+		let position = field.to_u32() as u64;
+		let size = {
+			let mut size_bytes = [0u8; 4];
+			self.file.read_exact_at(&mut size_bytes, position)?;
+			u32::from_be_bytes(size_bytes) as usize
+		};
+		let mut buffer = vec![0u8; size];
+		self.file.read_exact_at(&mut buffer, position + 4)?;
+		V::from_value_bytes(buffer)
+	}
+}
+
+impl<V: BytesValue> ValueStore<V> for BytesValueStore<V> {
+	fn write_value(&mut self, value: &V) -> Result<ValueField> {
+		let pos = self.file.seek(SeekFrom::End(0))?;
+		let position = u32::try_from(pos).map_err(|_| io::Error::other("value store is full"))?;
+		let bytes = value.to_value_bytes();
+		let size = u32::try_from(bytes.len()).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+		self.file.write_all(&size.to_be_bytes())?;
+		self.file.write_all(&bytes)?;
+		Ok(ValueField(position))
+	}
+	fn sync(&self) -> Result<()> {
+		self.file.sync_data()?;
+		Ok(())
+	}
+}
+
+pub(crate) fn value_records_end(file: &File) -> Result<u64> {
+	let length = file.metadata()?.len();
+	let mut position = HEADER_BYTES;
+	while position + 4 <= length {
+		let mut size_bytes = [0u8; 4];
+		file.read_exact_at(&mut size_bytes, position)?;
+		let end = position + 4 + u32::from_be_bytes(size_bytes) as u64;
+		if end > length {
+			break;
+		}
+		position = end;
+	}
+	Ok(position)
+}
+
+impl BytesValue for String {
+	fn to_value_bytes(&self) -> Vec<u8> { self.as_bytes().to_vec() }
+	fn from_value_bytes(bytes: Vec<u8>) -> Result<Self> {
+		String::from_utf8(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error).into())
+	}
+}
+
+impl BytesValue for Vec<u8> {
+	fn to_value_bytes(&self) -> Vec<u8> { self.clone() }
+	fn from_value_bytes(bytes: Vec<u8>) -> Result<Self> { Ok(bytes) }
+}
+
+impl BytesValue for u64 {
+	fn to_value_bytes(&self) -> Vec<u8> { self.to_be_bytes().to_vec() }
+	fn from_value_bytes(bytes: Vec<u8>) -> Result<Self> {
+		let bytes = <[u8; 8]>::try_from(bytes).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+		Ok(u64::from_be_bytes(bytes))
+	}
+}
+
+impl BytesValue for i64 {
+	fn to_value_bytes(&self) -> Vec<u8> { self.to_be_bytes().to_vec() }
+	fn from_value_bytes(bytes: Vec<u8>) -> Result<Self> {
+		let bytes = <[u8; 8]>::try_from(bytes).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+		Ok(i64::from_be_bytes(bytes))
+	}
+}
+
+impl Value for String {
+	fn open_store(store_path: &Path) -> Result<Box<dyn ValueStore<Self>>> {
+		Ok(Box::new(BytesValueStore::<String>::open(store_path)?))
+	}
+}
+
+impl Value for Vec<u8> {
+	fn open_store(store_path: &Path) -> Result<Box<dyn ValueStore<Self>>> {
+		Ok(Box::new(BytesValueStore::<Vec<u8>>::open(store_path)?))
+	}
+}
+
+impl Value for u64 {
+	fn open_store(store_path: &Path) -> Result<Box<dyn ValueStore<Self>>> {
+		Ok(Box::new(BytesValueStore::<u64>::open(store_path)?))
+	}
+}
+
+impl Value for i64 {
+	fn open_store(store_path: &Path) -> Result<Box<dyn ValueStore<Self>>> {
+		Ok(Box::new(BytesValueStore::<i64>::open(store_path)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::tests::ready_test_dir;
+	use crate::value_store::{ReadValue, ValueStore};
+	use crate::value_store::bytes::BytesValueStore;
+
+	#[test]
+	fn vs_basic() {
+		let store_path = ready_test_dir("bytes-value-store").join("values");
+		let mut store = BytesValueStore::<String>::open(store_path).expect("open");
+		let first = store.write_value(&"Hello!".to_string()).expect("write first");
+		let second = store.write_value(&String::new()).expect("write second");
+		assert_eq!("Hello!".to_string(), store.read_value(first).expect("read first"));
+		assert_eq!(String::new(), store.read_value(second).expect("read second"));
+	}
+}
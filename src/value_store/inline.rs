@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::value_store::{ReadValue, Value, ValueField, ValueStore};
+
+pub trait InlineValue: Copy + 'static {
+	fn to_value_field(&self) -> ValueField;
+	fn from_value_field(field: ValueField) -> Self;
+}
+
+pub struct InlineValueStore<V: InlineValue>(PhantomData<V>);
+
+impl<V: InlineValue> InlineValueStore<V> {
+	pub fn new() -> Self { Self(PhantomData) }
+}
+
+impl<V: InlineValue> Default for InlineValueStore<V> {
+	fn default() -> Self { Self::new() }
+}
+
+impl<V: InlineValue> ReadValue<V> for InlineValueStore<V> {
+	fn read_value(&self, field: ValueField) -> Result<V> {
+		Ok(V::from_value_field(field))
+	}
+}
+
+impl<V: InlineValue> ValueStore<V> for InlineValueStore<V> {
+	fn write_value(&mut self, value: &V) -> Result<ValueField> {
+		Ok(value.to_value_field())
+	}
+	fn sync(&self) -> Result<()> { Ok(()) }
+}
+
+impl<V: InlineValue> Value for V {
+	fn open_store(_store_path: &Path) -> Result<Box<dyn ValueStore<Self>>> {
+		Ok(Box::new(InlineValueStore::<V>::new()))
+	}
+}
+
+impl InlineValue for u32 {
+	fn to_value_field(&self) -> ValueField { ValueField(*self) }
+	fn from_value_field(field: ValueField) -> Self { field.0 }
+}
+
+impl InlineValue for i32 {
+	fn to_value_field(&self) -> ValueField { ValueField(*self as u32) }
+	fn from_value_field(field: ValueField) -> Self { field.0 as i32 }
+}
+
+impl InlineValue for u16 {
+	fn to_value_field(&self) -> ValueField { ValueField(*self as u32) }
+	fn from_value_field(field: ValueField) -> Self { field.0 as u16 }
+}
+
+impl InlineValue for u8 {
+	fn to_value_field(&self) -> ValueField { ValueField(*self as u32) }
+	fn from_value_field(field: ValueField) -> Self { field.0 as u8 }
+}
+
+impl InlineValue for bool {
+	fn to_value_field(&self) -> ValueField { ValueField(*self as u32) }
+	fn from_value_field(field: ValueField) -> Self { field.0 != 0 }
+}
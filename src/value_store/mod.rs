@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use crate::error::Result;
+
+pub mod bytes;
+pub mod inline;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ValueField(pub(crate) u32);
+
+impl ValueField {
+	pub fn to_u32(&self) -> u32 { self.0 }
+}
+
+impl From<u32> for ValueField {
+	fn from(value: u32) -> Self { Self(value) }
+}
+
+pub trait Value: Sized + 'static {
+	fn open_store(store_path: &Path) -> Result<Box<dyn ValueStore<Self>>>;
+}
+
+pub trait ValueStore<V>: ReadValue<V> {
+	fn write_value(&mut self, value: &V) -> Result<ValueField>;
+	fn sync(&self) -> Result<()>;
+}
+
+impl<V, T: ValueStore<V> + ?Sized> ValueStore<V> for Box<T> {
+	fn write_value(&mut self, value: &V) -> Result<ValueField> {
+		self.as_mut().write_value(value)
+	}
+	fn sync(&self) -> Result<()> {
+		self.as_ref().sync()
+	}
+}
+
+pub trait ReadValue<V> {
+	fn read_value(&self, field: ValueField) -> Result<V>;
+}
+
+impl<V, T: ReadValue<V> + ?Sized> ReadValue<V> for Box<T> {
+	fn read_value(&self, field: ValueField) -> Result<V> {
+		self.as_ref().read_value(field)
+	}
+}
@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::header::{check_file, FileHeader, FileKind};
+use crate::item_stash::element::ElementStoreIndex;
+use crate::item_stash::element_read::ElementRead;
+use crate::item_stash::stash::store_path;
+use crate::key_store::{Key, ReadKey};
+use crate::key_store::bytes::BytesKeyStore;
+use crate::key_store::field::KeyField;
+use crate::key_store::index::KeyStoreIndex;
+use crate::key_store::ordered::OrderedKeyStore;
+use crate::key_store::string::StringKeyStore;
+use crate::key_store::u32::U32KeyStore;
+use crate::kv_forest::{commit_log_path, element_stash_path, key_store_path, RootIndex};
+use crate::kv_forest::array_map::ElementMap;
+use crate::kv_forest::commit_log::read_records;
+use crate::trie::{u32_from_bytes, u32_is_stash_index};
+
+#[derive(Debug, Default)]
+pub struct ForestReport {
+	pub roots: usize,
+	pub nodes: usize,
+	pub keys: usize,
+	pub issues: Vec<Issue>,
+}
+
+impl ForestReport {
+	pub fn is_ok(&self) -> bool { self.issues.is_empty() }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Issue {
+	BadRoot { root: RootIndex },
+	PointerOutOfBounds { element: ElementStoreIndex, target: ElementStoreIndex },
+	EmptySubTrie { element: ElementStoreIndex },
+	ChecksumMismatch { node: ElementStoreIndex },
+	UnresolvedKey { element: ElementStoreIndex },
+	InvalidUtf8Key { element: ElementStoreIndex },
+	MisplacedKey { element: ElementStoreIndex, depth: usize },
+	CountMismatch { node: ElementStoreIndex, expected: u32, stored: u32 },
+	MissingKeyStore,
+}
+
+pub fn check_forest(path: impl AsRef<Path>) -> Result<ForestReport> {
+	let forest_path = path.as_ref();
+	let roots = match File::open(commit_log_path(forest_path)) {
+		Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+		file => read_records(&file?)?.into_iter().map(|record| record.root_index).collect(),
+	};
+	let elements_path = store_path(element_stash_path(forest_path));
+	let element_count = {
+		let file = File::open(&elements_path)?;
+		check_file(&file, FileKind::Elements)?;
+		ElementStoreIndex::from_file_position(file.metadata()?.len()).0
+	};
+	let element_read = ElementRead::open(&elements_path)?;
+	let key_path = key_store_path(forest_path);
+	let key_file = match File::open(&key_path) {
+		Err(error) if error.kind() == ErrorKind::NotFound => {
+			let mut report = Checker::<u32, U32KeyStore>::new(element_read, element_count, None).check(&roots)?;
+			report.issues.push(Issue::MissingKeyStore);
+			return Ok(report);
+		}
+		file => file?,
+	};
+	match FileHeader::read(&key_file)?.kind {
+		FileKind::U32Keys => Checker::new(element_read, element_count, Some(U32KeyStore::open_read_only(&key_path)?)).check(&roots),
+		FileKind::StringKeys => Checker::new(element_read, element_count, Some(StringKeyStore::open_read_only(&key_path)?)).check(&roots),
+		FileKind::BytesKeys => Checker::<Vec<u8>, _>::new(element_read, element_count, Some(BytesKeyStore::open_read_only(&key_path)?)).check(&roots),
+		FileKind::U64Keys => Checker::new(element_read, element_count, Some(OrderedKeyStore::<u64>::open_read_only(&key_path)?)).check(&roots),
+		FileKind::I64Keys => Checker::new(element_read, element_count, Some(OrderedKeyStore::<i64>::open_read_only(&key_path)?)).check(&roots),
+		FileKind::I32Keys => Checker::new(element_read, element_count, Some(OrderedKeyStore::<i32>::open_read_only(&key_path)?)).check(&roots),
+		kind => Err(Error::WrongFileKind { expected: FileKind::U32Keys, found: kind }),
+	}
+}
+
+struct Checker<K: Key, R: ReadKey<K>> {
+	element_read: ElementRead,
+	element_count: u32,
+	key_store: Option<R>,
+	visited: HashSet<u32>,
+	report: ForestReport,
+	key: PhantomData<K>,
+}
+
+impl<K: Key, R: ReadKey<K>> Checker<K, R> {
+	fn new(element_read: ElementRead, element_count: u32, key_store: Option<R>) -> Self {
+		Self { element_read, element_count, key_store, visited: HashSet::new(), report: ForestReport::default(), key: PhantomData }
+	}
+	fn check(mut self, roots: &[RootIndex]) -> Result<ForestReport> {
+		for root in roots {
+			self.report.roots += 1;
+			let index = root.0.0;
+			if index >= self.element_count {
+				self.report.issues.push(Issue::BadRoot { root: *root });
+				continue;
+			}
+			let Some(node) = self.read_node(index, 1)? else {
+				continue;
+			};
+			let [left, right] = node[0];
+			if !u32_is_stash_index(left) {
+				self.report.issues.push(Issue::BadRoot { root: *root });
+				continue;
+			}
+			self.check_node(index, left, ElementMap(right), &mut Vec::new())?;
+		}
+		Ok(self.report)
+	}
+	fn check_node(&mut self, parent: u32, top: u32, map: ElementMap, shards: &mut Vec<u8>) -> Result<()> {
+		let count = map.count_ones();
+		if count == 0 {
+			return Ok(());
+		}
+		if top.checked_add(count).map(|checksum_index| checksum_index >= parent).unwrap_or(true) {
+			self.report.issues.push(Issue::PointerOutOfBounds { element: ElementStoreIndex(parent), target: ElementStoreIndex(top) });
+			return Ok(());
+		}
+		if !self.visited.insert(top) {
+			return Ok(());
+		}
+		self.report.nodes += 1;
+		let stored = u32_from_bytes(&self.element_read.read(ElementStoreIndex(top + count))?[4..8]);
+		if stored != count {
+			self.report.issues.push(Issue::CountMismatch { node: ElementStoreIndex(top), expected: count, stored });
+			return Ok(());
+		}
+		let Some(node) = self.read_node(top, count)? else {
+			return Ok(());
+		};
+		for viewing_index in 0..count {
+			let element_index = top + viewing_index;
+			let [left, right] = node[viewing_index as usize];
+			shards.push(map.to_key(viewing_index as usize));
+			if !u32_is_stash_index(left) {
+				self.check_key(element_index, KeyField(left), shards);
+			} else if right == 0 {
+				self.report.issues.push(Issue::EmptySubTrie { element: ElementStoreIndex(element_index) });
+			} else {
+				self.check_node(element_index, left, ElementMap(right), shards)?;
+			}
+			shards.pop();
+		}
+		Ok(())
+	}
+	fn check_key(&mut self, element_index: u32, field: KeyField, shards: &[u8]) {
+		self.report.keys += 1;
+		let element = ElementStoreIndex(element_index);
+		let Some(key_store) = &self.key_store else {
+			return;
+		};
+		let key = match key_store.read_key(KeyStoreIndex::from(&field)) {
+			Ok(key) => key,
+			Err(Error::InvalidUtf8Key(_)) => {
+				self.report.issues.push(Issue::InvalidUtf8Key { element });
+				return;
+			}
+			Err(_) => {
+				self.report.issues.push(Issue::UnresolvedKey { element });
+				return;
+			}
+		};
+		if let Some(depth) = (0..shards.len()).find(|depth| key.to_shard(*depth) != shards[*depth]) {
+			self.report.issues.push(Issue::MisplacedKey { element, depth });
+		}
+	}
+	fn read_node(&mut self, top: u32, count: u32) -> Result<Option<Vec<[u32; 2]>>> {
+		let node = match self.element_read.read_node(ElementStoreIndex(top), count) {
+			Err(Error::Checksum(node)) => {
+				self.report.issues.push(Issue::ChecksumMismatch { node });
+				return Ok(None);
+			}
+			read => read?,
+		};
+		Ok(Some(node.iter().map(|bytes| [u32_from_bytes(&bytes[0..4]), u32_from_bytes(&bytes[4..8])]).collect()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::fs::OpenOptions;
+	use std::os::unix::fs::FileExt;
+
+	use crate::header::HEADER_BYTES;
+	use crate::item_stash::element::{ElementStoreIndex, node_checksum};
+	use crate::kv_forest::KvForest;
+	use crate::trie::{u32_from_bytes, u32_to_bytes};
+	use crate::tests::ready_test_dir;
+	use crate::verify::{check_forest, Issue};
+
+	#[test]
+	fn clean_forest_passes() {
+		let forest_path = ready_test_dir("verify-clean").join("forest");
+		let mut forest = KvForest::<String>::open(&forest_path).expect("open");
+		let mut batch = forest.batch(forest.latest_root()).expect("batch");
+		for i in 0..200 {
+			batch.push(format!("key-{}", i * 7), i).expect("push");
+		}
+		let root = batch.commit().expect("commit");
+		let root = forest.remove(root, &"key-14".to_string()).expect("remove");
+		forest.commit(root).expect("commit");
+		let report = check_forest(&forest_path).expect("check");
+		assert!(report.is_ok(), "{:?}", report.issues);
+		assert_eq!(2, report.roots);
+		assert!(report.keys >= 200);
+	}
+
+	#[test]
+	fn corrupt_key_is_reported() {
+		let forest_path = ready_test_dir("verify-corrupt").join("forest");
+		let mut forest = KvForest::<String>::open(&forest_path).expect("open");
+		let root = forest.push(forest.latest_root(), "alpha".to_string(), 1).expect("push");
+		let root = forest.push(root, "beta".to_string(), 2).expect("push");
+		forest.commit(root).expect("commit");
+		let keys = OpenOptions::new().write(true).open(forest_path.join("keys.stash")).expect("open keys");
+		keys.write_all_at(&[0xff], HEADER_BYTES + 2).expect("corrupt key");
+		let report = check_forest(&forest_path).expect("check");
+		assert!(matches!(report.issues.as_slice(), [Issue::InvalidUtf8Key { .. }]), "{:?}", report.issues);
+	}
+
+	#[test]
+	fn flipped_bit_is_reported() {
+		let forest_path = ready_test_dir("verify-flipped").join("forest");
+		let mut forest = KvForest::<u32>::open(&forest_path).expect("open");
+		let root = forest.push(forest.latest_root(), 1, 10).expect("push");
+		forest.commit(root).expect("commit");
+		let elements = OpenOptions::new().write(true).open(forest_path.join("elements.stash").join("elements.store")).expect("open elements");
+		elements.write_all_at(&[0xff], HEADER_BYTES + 2 * 8 + 7).expect("flip value");
+		let report = check_forest(&forest_path).expect("check");
+		assert!(matches!(report.issues.as_slice(), [Issue::ChecksumMismatch { .. }]), "{:?}", report.issues);
+	}
+
+	#[test]
+	fn map_disagreeing_with_node_count_is_reported() {
+		let forest_path = ready_test_dir("verify-count").join("forest");
+		let mut forest = KvForest::<u32>::open(&forest_path).expect("open");
+		let mut root = forest.latest_root();
+		for i in 0..64 {
+			root = forest.push(root, i << 26, i).expect("push");
+		}
+		forest.commit(root).expect("commit");
+		let elements = OpenOptions::new().read(true).write(true).open(forest_path.join("elements.stash").join("elements.store")).expect("open elements");
+		let root_index = ElementStoreIndex(root.0.0);
+		let mut element = [0u8; 8];
+		elements.read_exact_at(&mut element, root_index.to_file_position()).expect("read root");
+		let map = u32_from_bytes(&element[4..8]);
+		let count = map.count_ones();
+		assert!(count > 1);
+		element[4..8].copy_from_slice(&u32_to_bytes(map & (map - 1)));
+		let mut trailer = u32_to_bytes(node_checksum(root_index, &element)).to_vec();
+		trailer.extend_from_slice(&u32_to_bytes(1));
+		elements.write_all_at(&element, root_index.to_file_position()).expect("write root");
+		elements.write_all_at(&trailer, root_index.to_file_position() + 8).expect("write trailer");
+		let report = check_forest(&forest_path).expect("check");
+		assert!(matches!(report.issues.as_slice(), [Issue::CountMismatch { expected, .. }] if *expected == count - 1), "{:?}", report.issues);
+	}
+
+	#[test]
+	fn missing_key_store_is_reported() {
+		let forest_path = ready_test_dir("verify-missing-keys").join("forest");
+		let mut forest = KvForest::<u32>::open(&forest_path).expect("open");
+		let root = forest.push(forest.latest_root(), 1, 10).expect("push");
+		forest.commit(root).expect("commit");
+		drop(forest);
+		let key_path = forest_path.join("keys.stash");
+		fs::remove_file(&key_path).expect("remove keys");
+		let report = check_forest(&forest_path).expect("check");
+		assert_eq!(vec![Issue::MissingKeyStore], report.issues);
+		assert!(!key_path.exists());
+	}
+}